@@ -12,6 +12,7 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use fasyslog::sender::Sender;
 use fasyslog::Severity;
 
 #[cfg(unix)]
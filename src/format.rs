@@ -14,8 +14,12 @@
 
 //! Format Syslog messages according to the referred standards.
 
+use std::borrow::Cow;
 use std::fmt;
 use std::fmt::Formatter;
+use std::io;
+use std::io::Write;
+use std::sync::Arc;
 
 use jiff::Timestamp;
 use jiff::Zoned;
@@ -24,9 +28,259 @@ use crate::internal::hostname;
 use crate::Facility;
 use crate::SDElement;
 use crate::Severity;
+use crate::SeverityMask;
 
 const NILVALUE: &str = "-";
 
+/// A pluggable format for Syslog messages.
+///
+/// Implementations write a complete message, header included, to `output`. The built-in formats
+/// are [`RFC3164Format`] and [`RFC5424Format`]; users can implement this trait to send messages in
+/// a custom format with [`send_with`] or by setting it as the [default format] of a context.
+///
+/// [`send_with`]: crate::sender::Sender::send_with
+/// [default format]: SyslogContext::format
+pub trait SyslogFormat: fmt::Debug + Send + Sync {
+    /// Format the message with the given context and severity into `output`.
+    fn format(
+        &self,
+        context: &SyslogContext,
+        severity: Severity,
+        message: &dyn fmt::Display,
+        output: &mut Vec<u8>,
+    ) -> io::Result<()>;
+
+    /// Returns the MSGID carried by the format, if any.
+    ///
+    /// Senders that do not transfer formatted messages, such as the journald sender, use this to
+    /// map the MSGID to their own representation.
+    fn msgid(&self) -> Option<&str> {
+        None
+    }
+
+    /// Returns the structured data carried by the format.
+    ///
+    /// See also [`SyslogFormat::msgid`].
+    fn structured_data(&self) -> &[SDElement] {
+        &[]
+    }
+}
+
+impl<F: SyslogFormat + ?Sized> SyslogFormat for &F {
+    fn format(
+        &self,
+        context: &SyslogContext,
+        severity: Severity,
+        message: &dyn fmt::Display,
+        output: &mut Vec<u8>,
+    ) -> io::Result<()> {
+        (**self).format(context, severity, message, output)
+    }
+
+    fn msgid(&self) -> Option<&str> {
+        (**self).msgid()
+    }
+
+    fn structured_data(&self) -> &[SDElement] {
+        (**self).structured_data()
+    }
+}
+
+impl<F: SyslogFormat + ?Sized> SyslogFormat for Box<F> {
+    fn format(
+        &self,
+        context: &SyslogContext,
+        severity: Severity,
+        message: &dyn fmt::Display,
+        output: &mut Vec<u8>,
+    ) -> io::Result<()> {
+        (**self).format(context, severity, message, output)
+    }
+
+    fn msgid(&self) -> Option<&str> {
+        (**self).msgid()
+    }
+
+    fn structured_data(&self) -> &[SDElement] {
+        (**self).structured_data()
+    }
+}
+
+impl<F: SyslogFormat + ?Sized> SyslogFormat for Arc<F> {
+    fn format(
+        &self,
+        context: &SyslogContext,
+        severity: Severity,
+        message: &dyn fmt::Display,
+        output: &mut Vec<u8>,
+    ) -> io::Result<()> {
+        (**self).format(context, severity, message, output)
+    }
+
+    fn msgid(&self) -> Option<&str> {
+        (**self).msgid()
+    }
+
+    fn structured_data(&self) -> &[SDElement] {
+        (**self).structured_data()
+    }
+}
+
+/// The [RFC-3164] (BSD syslog Protocol) format.
+///
+/// This is the default format of a [`SyslogContext`].
+///
+/// [RFC-3164]: https://datatracker.ietf.org/doc/html/rfc3164
+#[derive(Debug, Default, Clone, Copy)]
+pub struct RFC3164Format;
+
+impl SyslogFormat for RFC3164Format {
+    fn format(
+        &self,
+        context: &SyslogContext,
+        severity: Severity,
+        message: &dyn fmt::Display,
+        output: &mut Vec<u8>,
+    ) -> io::Result<()> {
+        let formatter = context.format_rfc3164(severity, Some(message));
+        write!(output, "{formatter}")
+    }
+}
+
+/// The [RFC 5424] (The Syslog Protocol) format with the given MSGID and structured data.
+///
+/// [RFC 5424]: https://datatracker.ietf.org/doc/html/rfc5424
+#[derive(Debug, Default, Clone)]
+pub struct RFC5424Format {
+    msgid: Option<String>,
+    elements: Vec<SDElement>,
+}
+
+impl RFC5424Format {
+    /// Create a new RFC-5424 format with the given MSGID and structured data.
+    pub fn new<S: Into<String>>(msgid: Option<S>, elements: Vec<SDElement>) -> Self {
+        let msgid = msgid.map(|s| s.into());
+        Self { msgid, elements }
+    }
+}
+
+impl SyslogFormat for RFC5424Format {
+    fn format(
+        &self,
+        context: &SyslogContext,
+        severity: Severity,
+        message: &dyn fmt::Display,
+        output: &mut Vec<u8>,
+    ) -> io::Result<()> {
+        let formatter = RFC5424Formatter {
+            context,
+            severity,
+            msgid: self.msgid.as_deref().map(Cow::Borrowed),
+            elements: Cow::Borrowed(&self.elements),
+            message: Some(message),
+        };
+        write!(output, "{formatter}")
+    }
+
+    fn msgid(&self) -> Option<&str> {
+        self.msgid.as_deref()
+    }
+
+    fn structured_data(&self) -> &[SDElement] {
+        &self.elements
+    }
+}
+
+/// The format used by glibc's [syslog(3)] when writing to a local socket such as `/dev/log`.
+///
+/// This is [`RFC3164Format`] without the HOSTNAME field, since the local syslog daemon fills it in
+/// itself: `<PRI>Mmm dd hh:mm:ss TAG[PID]: MSG`.
+///
+/// [syslog(3)]: https://www.man7.org/linux/man-pages/man3/syslog.3.html
+#[derive(Debug, Default, Clone, Copy)]
+pub struct LocalFormat;
+
+impl SyslogFormat for LocalFormat {
+    fn format(
+        &self,
+        context: &SyslogContext,
+        severity: Severity,
+        message: &dyn fmt::Display,
+        output: &mut Vec<u8>,
+    ) -> io::Result<()> {
+        let formatter = context.format_local(severity, Some(message));
+        write!(output, "{formatter}")
+    }
+}
+
+/// The format understood by systemd for services logging to stdout or stderr, as defined in
+/// [sd-daemon(3)].
+///
+/// Every line of the message is prefixed with `<N>`, where `N` is the numerical code of the
+/// severity, or the full PRI value if [`with_facility`](SdDaemonFormat::with_facility) is set. The
+/// header fields of the context are not included since the journal adds them itself.
+///
+/// [sd-daemon(3)]: https://www.freedesktop.org/software/systemd/man/latest/sd-daemon.html
+#[derive(Debug, Default, Clone, Copy)]
+pub struct SdDaemonFormat {
+    with_facility: bool,
+}
+
+impl SdDaemonFormat {
+    /// Create a new sd-daemon format that prefixes lines with the severity only.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set whether to prefix lines with the full PRI value, including the facility of the context.
+    pub fn with_facility(mut self, with_facility: bool) -> Self {
+        self.with_facility = with_facility;
+        self
+    }
+}
+
+impl SyslogFormat for SdDaemonFormat {
+    fn format(
+        &self,
+        context: &SyslogContext,
+        severity: Severity,
+        message: &dyn fmt::Display,
+        output: &mut Vec<u8>,
+    ) -> io::Result<()> {
+        let prefix = if self.with_facility {
+            (context.facility.code() << 3) | severity.code()
+        } else {
+            severity.code()
+        };
+        // systemd reads the prefix of every line, so each line must carry it.
+        let message = message.to_string();
+        for (i, line) in message.split('\n').enumerate() {
+            if i > 0 {
+                output.push(b'\n');
+            }
+            write!(output, "<{prefix}>{line}")?;
+        }
+        Ok(())
+    }
+}
+
+/// How to send messages that span multiple lines.
+///
+/// Stack traces and other multi-line messages may break non-transparent framing of stream senders,
+/// or end up as a single oversized datagram. Splitting them sends every non-empty line as its own
+/// record with the same header fields.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub enum Multiline {
+    /// Send the message as a single record.
+    #[default]
+    Keep,
+    /// Send every line of the message as a separate record.
+    Split,
+    /// Send every line of the message as a separate record, and prefix every line but the first
+    /// with the given marker so that the receiver can reassemble the message.
+    SplitWithPrefix(Cow<'static, str>),
+}
+
 /// Shared context for constructing Syslog messages.
 #[derive(Debug, Clone)]
 pub struct SyslogContext {
@@ -34,6 +288,10 @@ pub struct SyslogContext {
     hostname: Option<String>,
     appname: Option<String>,
     procid: Option<String>,
+    format: Option<Arc<dyn SyslogFormat>>,
+    multiline: Multiline,
+    max_length: Option<usize>,
+    mask: SeverityMask,
 }
 
 impl Default for SyslogContext {
@@ -50,6 +308,10 @@ impl SyslogContext {
             hostname: None,
             appname: None,
             procid: None,
+            format: None,
+            multiline: Multiline::Keep,
+            max_length: None,
+            mask: SeverityMask::ALL,
         }
     }
 
@@ -68,6 +330,10 @@ impl SyslogContext {
             hostname,
             appname,
             procid: Some(procid.to_string()),
+            format: None,
+            multiline: Multiline::Keep,
+            max_length: None,
+            mask: SeverityMask::ALL,
         }
     }
 
@@ -95,8 +361,157 @@ impl SyslogContext {
         self
     }
 
+    /// Returns the facility of the Syslog message.
+    pub fn get_facility(&self) -> Facility {
+        self.facility
+    }
+
+    /// Returns the hostname of the Syslog message, if any.
+    pub fn get_hostname(&self) -> Option<&str> {
+        self.hostname.as_deref()
+    }
+
+    /// Returns the appname of the Syslog message, if any.
+    pub fn get_appname(&self) -> Option<&str> {
+        self.appname.as_deref()
+    }
+
+    /// Returns the procid of the Syslog message, if any.
+    pub fn get_procid(&self) -> Option<&str> {
+        self.procid.as_deref()
+    }
+
+    /// Set the default format of the Syslog message.
+    ///
+    /// The default format is used by the `send` method of senders. If not set, messages are
+    /// formatted as [`RFC3164Format`].
+    pub fn format(&mut self, format: impl SyslogFormat + 'static) -> &mut Self {
+        self.format = Some(Arc::new(format));
+        self
+    }
+
+    /// Returns the default format if one has been set.
+    pub(crate) fn default_format(&self) -> Option<Arc<dyn SyslogFormat>> {
+        self.format.clone()
+    }
+
+    /// Set how to send messages that span multiple lines.
+    ///
+    /// Default is [`Multiline::Keep`].
+    pub fn multiline(&mut self, multiline: Multiline) -> &mut Self {
+        self.multiline = multiline;
+        self
+    }
+
+    /// Set the maximum length in octets of a formatted Syslog message.
+    ///
+    /// Longer messages are truncated before they are sent. For example, [RFC-5426] §3.2 requires
+    /// receivers to accept messages of at least 480 octets over UDP, and recommends supporting
+    /// 2048 octets. Default is no limit.
+    ///
+    /// [RFC-5426]: https://datatracker.ietf.org/doc/html/rfc5426#section-3.2
+    pub fn max_length(&mut self, max_length: usize) -> &mut Self {
+        self.max_length = Some(max_length);
+        self
+    }
+
+    /// Set the severities of the messages to send, like [setlogmask(3)].
+    ///
+    /// Messages of other severities are dropped by senders before they are formatted. Default is
+    /// [`SeverityMask::ALL`].
+    ///
+    /// [setlogmask(3)]: https://man7.org/linux/man-pages/man3/setlogmask.3.html
+    pub fn mask(&mut self, mask: SeverityMask) -> &mut Self {
+        self.mask = mask;
+        self
+    }
+
+    /// Returns the severities of the messages to send.
+    pub fn get_mask(&self) -> SeverityMask {
+        self.mask
+    }
+
+    /// Returns `true` if messages of the given severity are sent.
+    ///
+    /// This can be used to skip building expensive messages that would be dropped anyway.
+    pub fn enabled(&self, severity: Severity) -> bool {
+        self.mask.contains(severity)
+    }
+
+    /// Truncate the formatted message to the maximum length, without splitting a UTF-8 character.
+    pub(crate) fn truncate(&self, formatted: &mut Vec<u8>) {
+        if let Some(max_length) = self.max_length {
+            if formatted.len() > max_length {
+                let mut len = max_length;
+                while len > 0 && (formatted[len] & 0xC0) == 0x80 {
+                    len -= 1;
+                }
+                formatted.truncate(len);
+            }
+        }
+    }
+
+    /// Split the message into the MSG part of the records to send, according to the multiline
+    /// mode.
+    pub(crate) fn split_message(&self, message: &dyn fmt::Display) -> Vec<String> {
+        let message = message.to_string();
+        let prefix = match &self.multiline {
+            Multiline::Keep => return vec![message],
+            Multiline::Split => "",
+            Multiline::SplitWithPrefix(prefix) => prefix.as_ref(),
+        };
+        let records = message
+            .lines()
+            .filter(|line| !line.is_empty())
+            .enumerate()
+            .map(|(i, line)| match i {
+                0 => line.to_string(),
+                _ => format!("{prefix}{line}"),
+            })
+            .collect::<Vec<_>>();
+        if records.is_empty() {
+            vec![message]
+        } else {
+            records
+        }
+    }
+
+    /// Split the message into the MSG part of the records to send, or none if the severity is not
+    /// enabled.
+    pub(crate) fn records(&self, severity: Severity, message: &dyn fmt::Display) -> Vec<String> {
+        if !self.enabled(severity) {
+            return Vec::new();
+        }
+        self.split_message(message)
+    }
+
+    /// Format the records of the message with the given severity in the given format, truncated
+    /// to the maximum length.
+    ///
+    /// This is what senders do in [`Sender::send_message`](crate::sender::Sender::send_message)
+    /// before sending each record.
+    pub(crate) fn format_message(
+        &self,
+        format: &dyn SyslogFormat,
+        severity: Severity,
+        message: &dyn fmt::Display,
+    ) -> io::Result<Vec<Vec<u8>>> {
+        let mut records = Vec::new();
+        for message in self.records(severity, message) {
+            let mut formatted = Vec::new();
+            format.format(self, severity, &message, &mut formatted)?;
+            self.truncate(&mut formatted);
+            records.push(formatted);
+        }
+        Ok(records)
+    }
+
     /// Format the Syslog message with the given severity as defined in RFC-3164.
-    pub fn format_rfc3164<M>(&self, severity: Severity, message: Option<M>) -> RFC3164Formatter<M> {
+    pub fn format_rfc3164<M>(
+        &self,
+        severity: Severity,
+        message: Option<M>,
+    ) -> RFC3164Formatter<'_, M> {
         RFC3164Formatter {
             context: self,
             severity,
@@ -104,6 +519,16 @@ impl SyslogContext {
         }
     }
 
+    /// Format the Syslog message with the given severity as glibc's `syslog(3)` does for local
+    /// sockets.
+    pub fn format_local<M>(&self, severity: Severity, message: Option<M>) -> LocalFormatter<'_, M> {
+        LocalFormatter {
+            context: self,
+            severity,
+            message,
+        }
+    }
+
     /// Format the Syslog message with the given severity as defined in RFC-5424.
     pub fn format_rfc5424<S, M>(
         &self,
@@ -111,17 +536,17 @@ impl SyslogContext {
         msgid: Option<S>,
         elements: Vec<SDElement>,
         message: Option<M>,
-    ) -> RFC5424Formatter<M>
+    ) -> RFC5424Formatter<'_, M>
     where
         S: Into<String>,
         M: fmt::Display,
     {
-        let msgid = msgid.map(|s| s.into());
+        let msgid = msgid.map(|s| Cow::Owned(s.into()));
         RFC5424Formatter {
             context: self,
             severity,
             msgid,
-            elements,
+            elements: Cow::Owned(elements),
             message,
         }
     }
@@ -168,6 +593,43 @@ where
     }
 }
 
+/// Format the Syslog message as glibc's [syslog(3)] does for local sockets.
+///
+/// [syslog(3)]: https://www.man7.org/linux/man-pages/man3/syslog.3.html
+#[derive(Debug)]
+pub struct LocalFormatter<'a, M> {
+    context: &'a SyslogContext,
+    severity: Severity,
+    message: Option<M>,
+}
+
+impl<M> fmt::Display for LocalFormatter<'_, M>
+where
+    M: fmt::Display,
+{
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let pri = (self.context.facility.code() << 3) | self.severity.code();
+        // glibc formats the timestamp with "%h %e %T" in local time.
+        let ts = Zoned::now().strftime("%b %e %T");
+        write!(f, "<{pri}>{ts} ")?;
+        // glibc writes the tag (appname) and the pid separately, and only terminates the
+        // header with ": " when a tag is present.
+        if let Some(appname) = &self.context.appname {
+            write!(f, "{appname}")?;
+        }
+        if let Some(procid) = &self.context.procid {
+            write!(f, "[{procid}]")?;
+        }
+        if self.context.appname.is_some() {
+            write!(f, ": ")?;
+        }
+        if let Some(message) = &self.message {
+            write!(f, "{message}")?;
+        }
+        Ok(())
+    }
+}
+
 /// Format the Syslog message as [RFC 5424] (The Syslog Protocol)
 ///
 /// [RFC 5424]: https://datatracker.ietf.org/doc/html/rfc5424
@@ -175,8 +637,8 @@ where
 pub struct RFC5424Formatter<'a, M> {
     context: &'a SyslogContext,
     severity: Severity,
-    msgid: Option<String>,
-    elements: Vec<SDElement>,
+    msgid: Option<Cow<'a, str>>,
+    elements: Cow<'a, [SDElement]>,
     message: Option<M>,
 }
 
@@ -205,7 +667,7 @@ where
         if self.elements.is_empty() {
             write!(f, "-")?;
         } else {
-            for element in &self.elements {
+            for element in self.elements.iter() {
                 write!(f, "{element}")?;
             }
         }
@@ -215,3 +677,64 @@ where
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_split_message() {
+        let mut context = SyslogContext::const_new();
+        let message = "panicked at main.rs\n  0: foo\r\n\n  1: bar\n";
+        assert_eq!(context.split_message(&message), vec![message]);
+
+        context.multiline(Multiline::Split);
+        assert_eq!(
+            context.split_message(&message),
+            vec!["panicked at main.rs", "  0: foo", "  1: bar"]
+        );
+
+        context.multiline(Multiline::SplitWithPrefix("+ ".into()));
+        assert_eq!(
+            context.split_message(&message),
+            vec!["panicked at main.rs", "+   0: foo", "+   1: bar"]
+        );
+        assert_eq!(context.split_message(&""), vec![""]);
+    }
+
+    #[test]
+    fn test_local_format_omits_hostname() {
+        let mut context = SyslogContext::const_new();
+        context
+            .facility(Facility::DAEMON)
+            .hostname("example.com")
+            .appname("app")
+            .procid("42");
+        let mut output = Vec::new();
+        LocalFormat
+            .format(&context, Severity::ERROR, &"hello", &mut output)
+            .unwrap();
+        let output = String::from_utf8(output).unwrap();
+        // "<27>Mmm dd hh:mm:ss app[42]: hello"
+        assert!(output.starts_with("<27>"), "{output}");
+        assert_eq!(&output[4 + 15..], " app[42]: hello");
+        assert!(!output.contains("example.com"), "{output}");
+    }
+
+    #[test]
+    fn test_sd_daemon_format() {
+        let mut context = SyslogContext::const_new();
+        context.facility(Facility::DAEMON);
+        let format = |format: SdDaemonFormat, message: &str| {
+            let mut output = Vec::new();
+            format
+                .format(&context, Severity::ERROR, &message, &mut output)
+                .unwrap();
+            String::from_utf8(output).unwrap()
+        };
+
+        let format_with_facility = SdDaemonFormat::new().with_facility(true);
+        assert_eq!(format(SdDaemonFormat::new(), "a\nb"), "<3>a\n<3>b");
+        assert_eq!(format(format_with_facility, "a\nb"), "<27>a\n<27>b");
+    }
+}
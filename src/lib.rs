@@ -23,23 +23,37 @@
 //! * [RFC-3164 Formatter]: [The BSD syslog Protocol](https://datatracker.ietf.org/doc/html/rfc3164)
 //! * [RFC-5424 Formatter]: [The Syslog Protocol](https://datatracker.ietf.org/doc/html/rfc5424)
 //! * [`UdpSender`]: [RFC 5426 - Transmission of Syslog Messages over UDP](https://datatracker.ietf.org/doc/html/rfc5426)
+//!   * `BatchedUdpSender` sends many datagrams per system call, with `sendmmsg` on Linux.
 //! * [`TcpSender`]: [RFC 6587 - Transmission of Syslog Messages over TCP](https://datatracker.ietf.org/doc/html/rfc6587)
 //! * [`NativeTlsSender`]: [RFC 5425 - Transport Layer Security (TLS) Transport Mapping for Syslog](https://datatracker.ietf.org/doc/html/rfc5425)
 //!   * This implementation is based on [`native-tls`](https://crates.io/crates/native-tls) and
 //!     requires features `native-tls` turned on.
+//! * [`RustlsSender`]: the same TLS transport based on [`rustls`](https://crates.io/crates/rustls)
+//!   * This implementation requires features `rustls` turned on, and does not depend on OpenSSL.
 //! * (unix only) Unix domain socket sender (datagram or stream)
+//!   * Messages can be formatted like glibc's `syslog(3)` for local sockets with `unix_local`.
+//! * `StdioSender`: [sd-daemon(3)](https://www.freedesktop.org/software/systemd/man/latest/sd-daemon.html)
+//!   `<N>` prefixed lines on stdout or stderr
+//! * (linux only) `JournaldSender`: [Native Journal Protocol](https://systemd.io/JOURNAL_NATIVE_PROTOCOL/)
+//!   of systemd-journald
+//! * Async senders for [`tokio`](https://tokio.rs) in `sender::tokio`, which require features
+//!   `tokio` turned on.
+//!   * The async TLS sender requires features `tokio-native-tls` turned on.
 //!
 //! [RFC-3164 Formatter]: format::RFC3164Formatter
 //! [RFC-5424 Formatter]: format::RFC5424Formatter
 //! [`UdpSender`]: sender::UdpSender
 //! [`TcpSender`]: sender::TcpSender
 //! [`NativeTlsSender`]: sender::NativeTlsSender
+//! [`RustlsSender`]: sender::RustlsSender
 //!
 //! # Example
 //!
 //! Send a message to a remote syslog server:
 //!
 //! ```rust, no_run
+//! use fasyslog::sender::Sender;
+//!
 //! let mut sender = fasyslog::sender::tcp_well_known().unwrap();
 //! sender
 //!     .send_rfc3164(fasyslog::Severity::INFORMATIONAL, "Hello, syslog!")
@@ -62,6 +76,9 @@
 mod facility;
 pub use facility::*;
 
+mod selector;
+pub use selector::*;
+
 mod severity;
 pub use severity::*;
 
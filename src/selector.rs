@@ -0,0 +1,191 @@
+// Copyright 2024 FastLabs Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::io;
+use std::str::FromStr;
+
+use crate::Facility;
+use crate::Severity;
+use crate::SeverityMask;
+
+/// A filter over facilities and severities, written in the selector syntax of [syslog.conf(5)].
+///
+/// A selector is a list of `facilities.priority` pairs separated by `;`, applied from left to
+/// right. Facilities are a list of names separated by `,`, or `*` for all facilities. The
+/// priority selects, for the listed facilities:
+///
+/// * `info`: the severity and all more severe ones;
+/// * `=info`: only the severity;
+/// * `!info`: removes the severity and all more severe ones;
+/// * `!=info`: removes only the severity;
+/// * `*`: all severities;
+/// * `none`: no severity.
+///
+/// For example, `*.info;mail.none;authpriv.none` selects messages of severity `info` or more
+/// severe, except for the `mail` and `authpriv` facilities.
+///
+/// [syslog.conf(5)]: https://man7.org/linux/man-pages/man5/syslog.conf.5.html
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Selector {
+    masks: [SeverityMask; 24],
+}
+
+impl Selector {
+    /// Parse a selector written in the syntax of syslog.conf.
+    pub fn parse(selector: &str) -> io::Result<Self> {
+        let mut masks = [SeverityMask::NONE; 24];
+        for part in selector.split(';').map(str::trim) {
+            let (facilities, priority) = part
+                .split_once('.')
+                .ok_or_else(|| invalid_selector(selector, part))?;
+            let facilities = parse_facilities(facilities)
+                .ok_or_else(|| invalid_selector(selector, facilities))?;
+            let priority = priority.trim();
+            let (negate, severities) =
+                parse_priority(priority).ok_or_else(|| invalid_selector(selector, priority))?;
+            for facility in facilities {
+                let mask = &mut masks[usize::from(facility.code())];
+                *mask = if negate {
+                    SeverityMask::from_bits(mask.bits() & !severities.bits())
+                } else {
+                    SeverityMask::from_bits(mask.bits() | severities.bits())
+                };
+            }
+        }
+        Ok(Self { masks })
+    }
+
+    /// Returns `true` if messages of the given facility and severity are selected.
+    pub fn matches(&self, facility: Facility, severity: Severity) -> bool {
+        self.mask(facility).contains(severity)
+    }
+
+    /// Returns the severities selected for the given facility.
+    pub fn mask(&self, facility: Facility) -> SeverityMask {
+        self.masks[usize::from(facility.code())]
+    }
+}
+
+impl FromStr for Selector {
+    type Err = io::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::parse(s)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for Selector {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let selector = String::deserialize(deserializer)?;
+        Self::parse(&selector).map_err(serde::de::Error::custom)
+    }
+}
+
+fn parse_facilities(facilities: &str) -> Option<Vec<Facility>> {
+    if facilities.trim() == "*" {
+        return Some(
+            (0..24)
+                .filter_map(|code| Facility::try_from(code).ok())
+                .collect(),
+        );
+    }
+    facilities
+        .split(',')
+        .map(|facility| match &facility.trim().to_lowercase()[..] {
+            "security" => Some(Facility::AUTH),
+            facility => Facility::try_from(facility).ok(),
+        })
+        .collect()
+}
+
+/// Returns whether the priority removes severities, and the severities it adds or removes.
+fn parse_priority(priority: &str) -> Option<(bool, SeverityMask)> {
+    let priority = priority.to_lowercase();
+    if priority == "none" {
+        return Some((true, SeverityMask::ALL));
+    }
+    let (negate, rest) = match priority.strip_prefix('!') {
+        Some(rest) => (true, rest),
+        None => (false, &priority[..]),
+    };
+    let (exact, rest) = match rest.strip_prefix('=') {
+        Some(rest) => (true, rest),
+        None => (false, rest),
+    };
+    let severities = match rest {
+        "*" => SeverityMask::ALL,
+        severity => {
+            let severity = parse_severity(severity)?;
+            if exact {
+                SeverityMask::only(severity)
+            } else {
+                SeverityMask::up_to(severity)
+            }
+        }
+    };
+    Some((negate, severities))
+}
+
+fn parse_severity(severity: &str) -> Option<Severity> {
+    match severity {
+        "emerg" | "panic" => Some(Severity::EMERGENCY),
+        "crit" => Some(Severity::CRITICAL),
+        "err" => Some(Severity::ERROR),
+        "warn" => Some(Severity::WARNING),
+        "info" => Some(Severity::INFORMATIONAL),
+        severity => Severity::try_from(severity).ok(),
+    }
+}
+
+fn invalid_selector(selector: &str, part: &str) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidInput,
+        format!("invalid selector {selector:?}: unexpected {part:?}"),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_selector() {
+        let selector = Selector::parse("mail.info;*.err;kern.none").unwrap();
+        assert!(selector.matches(Facility::MAIL, Severity::INFORMATIONAL));
+        assert!(!selector.matches(Facility::MAIL, Severity::DEBUG));
+        assert!(selector.matches(Facility::USER, Severity::ERROR));
+        assert!(!selector.matches(Facility::USER, Severity::WARNING));
+        assert_eq!(selector.mask(Facility::KERN), SeverityMask::NONE);
+
+        let selector = Selector::parse("auth,authpriv.*").unwrap();
+        assert_eq!(selector.mask(Facility::AUTHPRIV), SeverityMask::ALL);
+        assert_eq!(selector.mask(Facility::USER), SeverityMask::NONE);
+
+        let selector = Selector::parse("*.=debug; local0.*;local0.!=info").unwrap();
+        assert!(selector.matches(Facility::CRON, Severity::DEBUG));
+        assert!(!selector.matches(Facility::CRON, Severity::INFORMATIONAL));
+        assert!(selector.matches(Facility::LOCAL0, Severity::NOTICE));
+        assert!(!selector.matches(Facility::LOCAL0, Severity::INFORMATIONAL));
+
+        let selector = Selector::parse("*.*;*.!warn").unwrap();
+        assert!(selector.matches(Facility::USER, Severity::NOTICE));
+        assert!(!selector.matches(Facility::USER, Severity::WARNING));
+
+        let err = Selector::parse("mail.info;bogus.err").unwrap_err();
+        assert!(err.to_string().contains("\"bogus\""), "{err}");
+        assert!(Selector::parse("mail").is_err());
+        assert!(Selector::parse("mail.!none").is_err());
+    }
+}
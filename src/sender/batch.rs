@@ -0,0 +1,386 @@
+// Copyright 2024 FastLabs Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::io;
+use std::net::ToSocketAddrs;
+use std::net::UdpSocket;
+use std::time::Duration;
+use std::time::Instant;
+
+use crate::format::SyslogContext;
+use crate::sender::Sender;
+use crate::sender::UdpSender;
+
+/// A syslog sender that sends messages to a UDP socket in batches.
+///
+/// Formatted messages are queued, and sent when the batch is full, when a message is queued after
+/// the oldest queued message has waited for the maximum latency, or when the sender is flushed.
+/// On Linux, a batch is sent with a single `sendmmsg` call; on other platforms, it is sent with a
+/// `send` call per message. Either way, every message is sent as its own datagram, as required by
+/// [RFC-5426] §3.1.
+///
+/// Unlike [`UdpSender`], messages are not sent until the batch is sent, so users should flush the
+/// sender periodically, and before exiting. Dropping the sender sends the queued messages.
+///
+/// # Example
+///
+/// ```rust, no_run
+/// use fasyslog::sender::BatchedUdpSender;
+/// use fasyslog::sender::Sender;
+///
+/// let mut sender = BatchedUdpSender::connect("0.0.0.0:0", "127.0.0.1:514").unwrap();
+/// sender.set_batch_size(128);
+/// for i in 0..1000 {
+///     sender
+///         .send_rfc3164(fasyslog::Severity::INFORMATIONAL, format!("message {i}"))
+///         .unwrap();
+/// }
+/// sender.flush().unwrap();
+/// ```
+///
+/// [RFC-5426]: https://datatracker.ietf.org/doc/html/rfc5426#section-3.1
+#[derive(Debug)]
+pub struct BatchedUdpSender {
+    socket: UdpSocket,
+    context: SyslogContext,
+    // the queued messages, concatenated, and the end offset of each of them
+    buffer: Vec<u8>,
+    ends: Vec<usize>,
+    batch: Batch,
+    oldest: Option<Instant>,
+    batch_size: usize,
+    max_latency: Duration,
+}
+
+impl BatchedUdpSender {
+    /// Connect to a UDP socket at the given address.
+    pub fn connect<L: ToSocketAddrs, R: ToSocketAddrs>(local: L, remote: R) -> io::Result<Self> {
+        let socket = UdpSocket::bind(local)?;
+        socket.connect(remote)?;
+        Ok(Self::new(socket))
+    }
+
+    /// Create a new batched UDP sender with the given socket.
+    ///
+    /// Note that the passed `socket` MUST be connected to the remote address.
+    pub fn new(socket: UdpSocket) -> Self {
+        Self {
+            socket,
+            context: SyslogContext::default(),
+            buffer: Vec::new(),
+            ends: Vec::new(),
+            batch: Batch::default(),
+            oldest: None,
+            batch_size: 64,
+            max_latency: Duration::from_millis(100),
+        }
+    }
+
+    /// Set the number of messages to send at once.
+    ///
+    /// Default is 64. A batch size of 0 is treated as 1.
+    pub fn set_batch_size(&mut self, batch_size: usize) {
+        self.batch_size = batch_size.max(1);
+    }
+
+    /// Set the maximum time a message waits in the queue for its batch to fill up.
+    ///
+    /// This is only checked when messages are sent; an idle sender keeps its queued messages until
+    /// it is flushed. Default is 100 milliseconds.
+    pub fn set_max_latency(&mut self, max_latency: Duration) {
+        self.max_latency = max_latency;
+    }
+
+    /// Returns the number of queued messages.
+    pub fn len(&self) -> usize {
+        self.ends.len()
+    }
+
+    /// Returns `true` if no message is queued.
+    pub fn is_empty(&self) -> bool {
+        self.ends.is_empty()
+    }
+
+    /// Set the context when formatting Syslog message.
+    pub fn set_context(&mut self, context: SyslogContext) {
+        self.context = context;
+    }
+
+    /// Mutate the context when formatting Syslog message.
+    pub fn mut_context(&mut self) -> &mut SyslogContext {
+        &mut self.context
+    }
+
+    /// Send the queued messages.
+    ///
+    /// If a message cannot be sent, it is dropped and the error is returned; the messages after it
+    /// stay queued.
+    fn send_queued(&mut self) -> io::Result<()> {
+        let mut sent = 0;
+        let mut result = Ok(());
+        while sent < self.ends.len() {
+            let start = if sent == 0 { 0 } else { self.ends[sent - 1] };
+            match self
+                .batch
+                .send(&self.socket, &self.buffer, start, &self.ends[sent..])
+            {
+                Ok(n) => sent += n,
+                Err(err) => {
+                    sent += 1;
+                    result = Err(err);
+                    break;
+                }
+            }
+        }
+
+        if sent == self.ends.len() {
+            self.buffer.clear();
+            self.ends.clear();
+            self.oldest = None;
+        } else {
+            let offset = self.ends[sent - 1];
+            self.buffer.drain(..offset);
+            self.ends.drain(..sent);
+            self.ends.iter_mut().for_each(|end| *end -= offset);
+        }
+        result
+    }
+}
+
+impl From<UdpSender> for BatchedUdpSender {
+    /// Batch the messages of the given sender, keeping its context.
+    fn from(sender: UdpSender) -> Self {
+        let (socket, context) = sender.into_parts();
+        let mut sender = Self::new(socket);
+        sender.context = context;
+        sender
+    }
+}
+
+impl Drop for BatchedUdpSender {
+    fn drop(&mut self) {
+        let _ = self.send_queued();
+    }
+}
+
+impl Sender for BatchedUdpSender {
+    fn send_formatted(&mut self, formatted: &[u8]) -> io::Result<()> {
+        let now = Instant::now();
+        let oldest = *self.oldest.get_or_insert(now);
+        self.buffer.extend_from_slice(formatted);
+        self.ends.push(self.buffer.len());
+        if self.ends.len() >= self.batch_size || now.duration_since(oldest) >= self.max_latency {
+            self.send_queued()?;
+        }
+        Ok(())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.send_queued()
+    }
+
+    fn context(&self) -> &SyslogContext {
+        &self.context
+    }
+
+    fn context_mut(&mut self) -> &mut SyslogContext {
+        &mut self.context
+    }
+}
+
+/// The messages of a `sendmmsg` call.
+///
+/// The vectors are kept between calls to save allocations, but they are empty outside of
+/// [`Batch::send`], so they never hold dangling pointers.
+#[cfg(target_os = "linux")]
+#[derive(Default)]
+struct Batch {
+    iovecs: Vec<nix::libc::iovec>,
+    headers: Vec<nix::libc::mmsghdr>,
+}
+
+// SAFETY: the raw pointers in the vectors are only set, and read, during a `Batch::send` call that
+// borrows the batch mutably, and the vectors are cleared before it returns
+#[cfg(target_os = "linux")]
+unsafe impl Send for Batch {}
+#[cfg(target_os = "linux")]
+unsafe impl Sync for Batch {}
+
+#[cfg(target_os = "linux")]
+impl std::fmt::Debug for Batch {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Batch").finish_non_exhaustive()
+    }
+}
+
+#[cfg(target_os = "linux")]
+impl Batch {
+    /// Send the messages of `buffer` from `start` to each of `ends` with a single `sendmmsg` call.
+    ///
+    /// Returns the number of messages sent, or the error of the first one if none was sent.
+    fn send(
+        &mut self,
+        socket: &UdpSocket,
+        buffer: &[u8],
+        mut start: usize,
+        ends: &[usize],
+    ) -> io::Result<usize> {
+        use std::os::fd::AsRawFd;
+
+        use nix::errno::Errno;
+        use nix::libc;
+
+        for &end in ends {
+            let datagram = &buffer[start..end];
+            self.iovecs.push(libc::iovec {
+                iov_base: datagram.as_ptr() as *mut libc::c_void,
+                iov_len: datagram.len(),
+            });
+            start = end;
+        }
+        for iovec in &mut self.iovecs {
+            // SAFETY: all-zero is a valid header, without address nor control messages
+            let mut header: libc::mmsghdr = unsafe { std::mem::zeroed() };
+            header.msg_hdr.msg_iov = iovec;
+            header.msg_hdr.msg_iovlen = 1;
+            self.headers.push(header);
+        }
+        // SAFETY: the headers point to the iovecs, which point into the buffer, and all of them
+        // outlive the call; the kernel only reads the messages
+        let sent = unsafe {
+            libc::sendmmsg(
+                socket.as_raw_fd(),
+                self.headers.as_mut_ptr(),
+                self.headers.len() as libc::c_uint,
+                0,
+            )
+        };
+        self.headers.clear();
+        self.iovecs.clear();
+        match Errno::result(sent) {
+            Ok(0) => Err(io::ErrorKind::WriteZero.into()),
+            Ok(sent) => Ok(sent as usize),
+            Err(errno) => Err(errno.into()),
+        }
+    }
+}
+
+/// Sends the messages of a batch one by one.
+#[cfg(not(target_os = "linux"))]
+#[derive(Debug, Default)]
+struct Batch;
+
+#[cfg(not(target_os = "linux"))]
+impl Batch {
+    /// Send the messages of `buffer` from `start` to each of `ends` with a `send` call each.
+    ///
+    /// Returns the number of messages sent, or the error of the first one if none was sent.
+    fn send(
+        &mut self,
+        socket: &UdpSocket,
+        buffer: &[u8],
+        mut start: usize,
+        ends: &[usize],
+    ) -> io::Result<usize> {
+        for (sent, &end) in ends.iter().enumerate() {
+            if let Err(err) = socket.send(&buffer[start..end]) {
+                return if sent == 0 { Err(err) } else { Ok(sent) };
+            }
+            start = end;
+        }
+        Ok(ends.len())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_send_batches() {
+        let receiver = UdpSocket::bind("127.0.0.1:0").unwrap();
+        receiver
+            .set_read_timeout(Some(Duration::from_secs(5)))
+            .unwrap();
+        let mut sender =
+            BatchedUdpSender::connect("127.0.0.1:0", receiver.local_addr().unwrap()).unwrap();
+        sender.set_batch_size(3);
+        sender.set_max_latency(Duration::from_secs(3600));
+
+        for message in ["a", "bb", "ccc", "dddd"] {
+            sender.send_formatted(message.as_bytes()).unwrap();
+        }
+        assert_eq!(sender.len(), 1);
+        sender.flush().unwrap();
+        assert!(sender.is_empty());
+
+        let mut buf = [0; 16];
+        for expected in ["a", "bb", "ccc", "dddd"] {
+            let len = receiver.recv(&mut buf).unwrap();
+            assert_eq!(&buf[..len], expected.as_bytes());
+        }
+    }
+
+    #[test]
+    fn test_max_latency() {
+        let receiver = UdpSocket::bind("127.0.0.1:0").unwrap();
+        receiver
+            .set_read_timeout(Some(Duration::from_secs(5)))
+            .unwrap();
+        let mut sender =
+            BatchedUdpSender::connect("127.0.0.1:0", receiver.local_addr().unwrap()).unwrap();
+        sender.set_batch_size(100);
+        sender.set_max_latency(Duration::from_millis(50));
+
+        sender.send_formatted(b"a").unwrap();
+        assert_eq!(sender.len(), 1);
+        std::thread::sleep(Duration::from_millis(60));
+        // the oldest message has waited long enough, so the batch is sent without a flush
+        sender.send_formatted(b"b").unwrap();
+        assert!(sender.is_empty());
+
+        let mut buf = [0; 16];
+        for expected in ["a", "b"] {
+            let len = receiver.recv(&mut buf).unwrap();
+            assert_eq!(&buf[..len], expected.as_bytes());
+        }
+    }
+
+    #[test]
+    fn test_drop_failed_message() {
+        let receiver = UdpSocket::bind("127.0.0.1:0").unwrap();
+        receiver
+            .set_read_timeout(Some(Duration::from_secs(5)))
+            .unwrap();
+        let mut sender =
+            BatchedUdpSender::connect("127.0.0.1:0", receiver.local_addr().unwrap()).unwrap();
+        sender.set_max_latency(Duration::from_secs(3600));
+
+        // a datagram larger than 65507 octets cannot be sent over IPv4
+        let huge = vec![b'x'; 70_000];
+        for message in [&b"a"[..], &huge, b"c"] {
+            sender.send_formatted(message).unwrap();
+        }
+        sender.flush().unwrap_err();
+        assert_eq!(sender.len(), 1);
+        sender.flush().unwrap();
+        assert!(sender.is_empty());
+
+        let mut buf = [0; 16];
+        for expected in ["a", "c"] {
+            let len = receiver.recv(&mut buf).unwrap();
+            assert_eq!(&buf[..len], expected.as_bytes());
+        }
+    }
+}
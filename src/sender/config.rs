@@ -0,0 +1,324 @@
+// Copyright 2024 FastLabs Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::io;
+
+use serde::de::value::StrDeserializer;
+use serde::Deserialize;
+
+use crate::sender::url::connect_tls;
+use crate::sender::url::invalid_input;
+use crate::sender::url::parse_host_port;
+use crate::sender::url::resolve;
+use crate::sender::url::set_context_param;
+use crate::sender::url::set_framing;
+use crate::sender::url::set_postfix;
+use crate::sender::url::TlsParams;
+use crate::sender::Framing;
+use crate::sender::Sender;
+use crate::sender::SyslogSender;
+
+/// The transport of a [`SenderConfig`].
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Transport {
+    /// UDP, see [`udp`](super::udp).
+    #[default]
+    Udp,
+    /// TCP, see [`tcp`](super::tcp).
+    Tcp,
+    /// TLS over TCP, requires features `native-tls` or `rustls` turned on. `native-tls` is used
+    /// when both are.
+    Tls,
+    /// UDP broadcast, see [`broadcast`](super::broadcast).
+    Broadcast,
+    /// (unix only) Unix domain socket, either datagram or stream.
+    Unix,
+    /// (unix only) Unix datagram socket.
+    UnixDgram,
+    /// (unix only) Unix stream socket.
+    UnixStream,
+    /// (linux only) The native journal protocol of systemd-journald.
+    Journald,
+    /// The standard output of the process.
+    Stdout,
+    /// The standard error of the process.
+    Stderr,
+}
+
+/// TLS settings of a [`SenderConfig`].
+#[derive(Debug, Default, Clone, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct TlsConfig {
+    /// The domain to validate the certificate against. Defaults to the host of the address.
+    pub domain: Option<String>,
+    /// The path to a PEM-encoded CA bundle to trust in addition to the system roots.
+    pub ca: Option<String>,
+    /// The path to a PEM-encoded client certificate chain, for mutual authentication.
+    pub cert: Option<String>,
+    /// The path to the PEM-encoded PKCS #8 private key of the client certificate.
+    pub key: Option<String>,
+}
+
+/// A deserializable description of a sender.
+///
+/// All fields are optional; the default configuration sends to UDP `127.0.0.1:514`.
+///
+/// # Example
+///
+/// ```rust, no_run
+/// let config: fasyslog::sender::SenderConfig = serde_json::from_str(
+///     r#"{
+///         "transport": "tcp",
+///         "address": "syslog.example.com:601",
+///         "framing": "octet-counting",
+///         "appname": "example",
+///         "facility": "local0"
+///     }"#,
+/// )
+/// .unwrap();
+/// let sender = config.with_env_overrides().unwrap().build().unwrap();
+/// ```
+#[derive(Debug, Default, Clone, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SenderConfig {
+    /// The transport to send messages over.
+    pub transport: Transport,
+    /// The address to send messages to: `host:port` for network transports, or a path for Unix
+    /// domain sockets and journald. Defaults to the well-known address of the transport.
+    pub address: Option<String>,
+    /// TLS settings, only used by [`Transport::Tls`].
+    pub tls: TlsConfig,
+    /// The framing of stream transports.
+    pub framing: Option<Framing>,
+    /// The postfix of non-transparent framing.
+    pub postfix: Option<String>,
+    /// The facility, such as `local0`.
+    pub facility: Option<String>,
+    /// The hostname.
+    pub hostname: Option<String>,
+    /// The appname.
+    pub appname: Option<String>,
+    /// The procid.
+    pub procid: Option<String>,
+    /// The default format: `rfc3164`, `rfc5424`, `local` or `sd-daemon`.
+    pub format: Option<String>,
+    /// How to send multi-line messages: `keep` or `split`.
+    pub multiline: Option<String>,
+    /// The maximum length in octets of a formatted message.
+    pub max_length: Option<usize>,
+}
+
+impl SenderConfig {
+    /// Override the configuration with environment variables, if set.
+    ///
+    /// * `SYSLOG_TRANSPORT` overrides [`transport`](SenderConfig::transport), with the same names
+    ///   as in the serialized form, such as `tcp` or `unix-stream`;
+    /// * `SYSLOG_ADDR` overrides [`address`](SenderConfig::address);
+    /// * `SYSLOG_FACILITY`, `SYSLOG_HOSTNAME` and `SYSLOG_APPNAME` override the context fields.
+    ///
+    /// Returns an error if `SYSLOG_TRANSPORT` is not a known transport.
+    pub fn with_env_overrides(self) -> io::Result<Self> {
+        self.with_overrides(|name| std::env::var(name).ok())
+    }
+
+    // Apply the overrides of `with_env_overrides`, looking variables up with `lookup`.
+    fn with_overrides(mut self, lookup: impl Fn(&str) -> Option<String>) -> io::Result<Self> {
+        let var = |name| lookup(name).filter(|value| !value.is_empty());
+        if let Some(transport) = var("SYSLOG_TRANSPORT") {
+            let deserializer = StrDeserializer::<serde::de::value::Error>::new(&transport);
+            self.transport = Transport::deserialize(deserializer)
+                .map_err(|_| invalid_input(format!("unknown transport: {transport}")))?;
+        }
+        if let Some(address) = var("SYSLOG_ADDR") {
+            self.address = Some(address);
+        }
+        if let Some(facility) = var("SYSLOG_FACILITY") {
+            self.facility = Some(facility);
+        }
+        if let Some(hostname) = var("SYSLOG_HOSTNAME") {
+            self.hostname = Some(hostname);
+        }
+        if let Some(appname) = var("SYSLOG_APPNAME") {
+            self.appname = Some(appname);
+        }
+        Ok(self)
+    }
+
+    /// Build a sender as configured.
+    pub fn build(&self) -> io::Result<SyslogSender> {
+        let address = self.address.as_deref();
+        let mut sender = match self.transport {
+            Transport::Udp => {
+                let (host, port) = parse_host_port(address.unwrap_or("127.0.0.1"), 514)?;
+                let remote = resolve(&host, port)?;
+                let local = if remote.is_ipv4() {
+                    "0.0.0.0:0"
+                } else {
+                    "[::]:0"
+                };
+                SyslogSender::Udp(super::udp(local, remote)?)
+            }
+            Transport::Tcp => {
+                let (host, port) = parse_host_port(address.unwrap_or("127.0.0.1"), 601)?;
+                SyslogSender::Tcp(super::tcp((host.as_str(), port))?)
+            }
+            Transport::Tls => {
+                let address = address
+                    .ok_or_else(|| invalid_input("address is required for TLS transport"))?;
+                let (host, port) = parse_host_port(address, 6514)?;
+                let params = TlsParams {
+                    domain: self.tls.domain.as_deref(),
+                    ca: self.tls.ca.as_deref(),
+                    cert: self.tls.cert.as_deref(),
+                    key: self.tls.key.as_deref(),
+                };
+                connect_tls(&host, port, &params)?
+            }
+            Transport::Broadcast => {
+                let (_, port) = parse_host_port(address.unwrap_or_default(), 514)?;
+                SyslogSender::Udp(super::broadcast(port)?)
+            }
+            #[cfg(unix)]
+            Transport::Unix => match address {
+                Some(path) => super::unix(path)?,
+                None => super::unix_well_known()?,
+            },
+            #[cfg(unix)]
+            Transport::UnixDgram => {
+                let path = address.ok_or_else(|| invalid_input("address is required"))?;
+                SyslogSender::UnixDatagram(super::unix_datagram(path)?)
+            }
+            #[cfg(unix)]
+            Transport::UnixStream => {
+                let path = address.ok_or_else(|| invalid_input("address is required"))?;
+                SyslogSender::UnixStream(super::unix_stream(path)?)
+            }
+            #[cfg(target_os = "linux")]
+            Transport::Journald => match address {
+                Some(path) => SyslogSender::Journald(super::journald(path)?),
+                None => SyslogSender::Journald(super::journald_well_known()?),
+            },
+            Transport::Stdout => SyslogSender::Stdio(super::stdout()),
+            Transport::Stderr => SyslogSender::Stdio(super::stderr()),
+            #[allow(unreachable_patterns)]
+            transport => {
+                return Err(io::Error::new(
+                    io::ErrorKind::Unsupported,
+                    format!("transport is not supported on this platform: {transport:?}"),
+                ))
+            }
+        };
+
+        if let Some(framing) = self.framing {
+            set_framing(&mut sender, framing)?;
+        }
+        if let Some(postfix) = &self.postfix {
+            set_postfix(&mut sender, postfix.clone())?;
+        }
+        let params = [
+            ("facility", &self.facility),
+            ("hostname", &self.hostname),
+            ("appname", &self.appname),
+            ("procid", &self.procid),
+            ("format", &self.format),
+            ("multiline", &self.multiline),
+        ];
+        let context = sender.context_mut();
+        for (name, value) in params {
+            if let Some(value) = value {
+                set_context_param(context, name, value)?;
+            }
+        }
+        if let Some(max_length) = self.max_length {
+            context.max_length(max_length);
+        }
+        Ok(sender)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Facility;
+
+    #[test]
+    fn test_build_from_json() {
+        let config: SenderConfig = serde_json::from_str(
+            r#"{
+                "transport": "udp",
+                "address": "127.0.0.1:5514",
+                "facility": "local3",
+                "appname": "example",
+                "max_length": 480
+            }"#,
+        )
+        .unwrap();
+        let sender = config.build().unwrap();
+        assert!(matches!(sender, SyslogSender::Udp(_)));
+        assert_eq!(sender.context().get_facility(), Facility::LOCAL3);
+        assert_eq!(sender.context().get_appname(), Some("example"));
+
+        let config: SenderConfig =
+            serde_json::from_str(r#"{ "transport": "udp", "framing": "octet-counting" }"#).unwrap();
+        assert!(config.build().is_err());
+        assert!(serde_json::from_str::<SenderConfig>(r#"{ "transport": "smtp" }"#).is_err());
+    }
+
+    #[test]
+    fn test_env_overrides() {
+        use std::collections::HashMap;
+        use std::io::Read;
+        use std::net::TcpListener;
+
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let config: SenderConfig = serde_json::from_str(
+            r#"{ "transport": "udp", "appname": "example", "hostname": "json.example.com" }"#,
+        )
+        .unwrap();
+        let vars = HashMap::from([
+            ("SYSLOG_TRANSPORT", "tcp".to_string()),
+            ("SYSLOG_ADDR", listener.local_addr().unwrap().to_string()),
+            ("SYSLOG_FACILITY", "local5".to_string()),
+            ("SYSLOG_APPNAME", "env-app".to_string()),
+            ("SYSLOG_HOSTNAME", String::new()),
+        ]);
+        let overridden = config
+            .clone()
+            .with_overrides(|name| vars.get(name).cloned())
+            .unwrap();
+        assert_eq!(overridden.transport, Transport::Tcp);
+        assert_eq!(overridden.appname.as_deref(), Some("env-app"));
+        // empty variables are ignored
+        assert_eq!(overridden.hostname, config.hostname);
+        assert_eq!(config.transport, Transport::Udp);
+
+        let mut sender = overridden.build().unwrap();
+        assert!(matches!(sender, SyslogSender::Tcp(_)));
+        assert_eq!(sender.context().get_facility(), Facility::LOCAL5);
+        let (mut stream, _) = listener.accept().unwrap();
+        sender.send_formatted(b"hello").unwrap();
+        sender.flush().unwrap();
+        drop(sender);
+        let mut received = String::new();
+        stream.read_to_string(&mut received).unwrap();
+        assert_eq!(received, "hello\r\n");
+
+        let err = config
+            .with_overrides(|name| (name == "SYSLOG_TRANSPORT").then(|| "smtp".to_string()))
+            .unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+        assert_eq!(err.to_string(), "unknown transport: smtp");
+    }
+}
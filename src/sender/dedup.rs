@@ -0,0 +1,252 @@
+// Copyright 2024 FastLabs Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::fmt;
+use std::io;
+use std::time::Duration;
+use std::time::Instant;
+
+use crate::format::SyslogContext;
+use crate::format::SyslogFormat;
+use crate::sender::Sender;
+use crate::Severity;
+
+/// A sender that folds identical consecutive messages into one.
+///
+/// A message with the same severity, message ID and body as the previous one is not sent.
+/// Instead, a `last message repeated N times` record is sent when a different message is sent,
+/// when the sender is flushed, or when a repeated message is sent after the timeout since the
+/// original message, in which case the repeated message is sent again as well. The record has the
+/// severity, format and message ID of the repeated message, so this works with both RFC 3164 and
+/// RFC 5424 output.
+///
+/// The record is emitted lazily, as there is no timer: repeats of the last message are only
+/// reported by the next different message or flush. Flush the sender periodically, and before
+/// dropping it, so that the last repeats are not lost. If sending the record fails, the repeats
+/// are kept and reported again by the next different message or flush.
+///
+/// # Example
+///
+/// ```rust, no_run
+/// use fasyslog::sender::DedupSender;
+/// use fasyslog::sender::Sender;
+///
+/// let unix = fasyslog::sender::unix_well_known().unwrap();
+/// let mut sender = DedupSender::new(unix);
+/// for _ in 0..10 {
+///     sender
+///         .send_rfc3164(fasyslog::Severity::WARNING, "health check failed")
+///         .unwrap();
+/// }
+/// sender.flush().unwrap();
+/// ```
+#[derive(Debug)]
+pub struct DedupSender<S> {
+    sender: S,
+    context: SyslogContext,
+    timeout: Duration,
+    last: Option<Last>,
+}
+
+/// The last message sent, and the summary of its repeats.
+#[derive(Debug)]
+struct Last {
+    severity: Severity,
+    msgid: Option<String>,
+    body: String,
+    sent: Instant,
+    repeated: u64,
+    summary: Vec<Vec<u8>>,
+}
+
+impl<S: Sender> DedupSender<S> {
+    /// Create a new deduplicating sender, formatting messages with a copy of the context of
+    /// `sender`.
+    pub fn new(sender: S) -> Self {
+        Self {
+            context: sender.context().clone(),
+            sender,
+            timeout: Duration::from_secs(30),
+            last: None,
+        }
+    }
+
+    /// Set the context when formatting Syslog message.
+    pub fn set_context(&mut self, context: SyslogContext) {
+        self.context = context;
+    }
+
+    /// Set how long repeats of a message are folded before it is sent again.
+    ///
+    /// Default is 30 seconds.
+    pub fn set_timeout(&mut self, timeout: Duration) {
+        self.timeout = timeout;
+    }
+
+    /// Returns the wrapped sender.
+    pub fn get_ref(&self) -> &S {
+        &self.sender
+    }
+
+    /// Mutate the wrapped sender.
+    pub fn get_mut(&mut self) -> &mut S {
+        &mut self.sender
+    }
+
+    /// Send the summary of the repeats of the last message, if any.
+    fn report(&mut self) -> io::Result<()> {
+        if let Some(last) = &mut self.last {
+            if last.repeated > 0 {
+                // only forget the repeats once they are reported
+                while let Some(summary) = last.summary.first() {
+                    self.sender.send_formatted(summary)?;
+                    last.summary.remove(0);
+                }
+                last.repeated = 0;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<S: Sender> Sender for DedupSender<S> {
+    fn send_formatted(&mut self, formatted: &[u8]) -> io::Result<()> {
+        self.report()?;
+        self.last = None;
+        self.sender.send_formatted(formatted)
+    }
+
+    /// Send the summary of the repeats of the last message, if any, and flush the wrapped sender.
+    fn flush(&mut self) -> io::Result<()> {
+        self.report()?;
+        self.sender.flush()
+    }
+
+    fn context(&self) -> &SyslogContext {
+        &self.context
+    }
+
+    fn context_mut(&mut self) -> &mut SyslogContext {
+        &mut self.context
+    }
+
+    fn send_message(
+        &mut self,
+        format: &dyn SyslogFormat,
+        severity: Severity,
+        message: &dyn fmt::Display,
+    ) -> io::Result<()> {
+        if !self.context.enabled(severity) {
+            return Ok(());
+        }
+        let body = message.to_string();
+        let msgid = format.msgid();
+        if let Some(last) = &mut self.last {
+            let repeated = last.severity == severity
+                && last.msgid.as_deref() == msgid
+                && last.body == body
+                && last.sent.elapsed() < self.timeout;
+            if repeated {
+                // format the summary now, since the format is only borrowed for this call
+                last.repeated += 1;
+                let summary = format!("last message repeated {} times", last.repeated);
+                last.summary = self.context.format_message(format, severity, &summary)?;
+                return Ok(());
+            }
+        }
+
+        self.report()?;
+        for formatted in self.context.format_message(format, severity, &body)? {
+            self.sender.send_formatted(&formatted)?;
+        }
+        self.last = Some(Last {
+            severity,
+            msgid: msgid.map(str::to_string),
+            body,
+            sent: Instant::now(),
+            repeated: 0,
+            summary: Vec::new(),
+        });
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::format::RFC5424Format;
+    use crate::sender::testing::Recorder;
+
+    #[test]
+    fn test_fold_repeated_messages() {
+        let recorder = Recorder::default();
+        let mut sender = DedupSender::new(recorder.clone());
+
+        for _ in 0..3 {
+            sender.send_rfc3164(Severity::WARNING, "flapping").unwrap();
+        }
+        sender.send_rfc3164(Severity::ERROR, "flapping").unwrap();
+        let check = RFC5424Format::new(Some("check"), Vec::new());
+        sender
+            .send_with(&check, Severity::ERROR, "flapping")
+            .unwrap();
+        sender
+            .send_with(&check, Severity::ERROR, "flapping")
+            .unwrap();
+        sender.flush().unwrap();
+
+        let sent = recorder.sent();
+        assert_eq!(sent.len(), 5);
+        assert!(sent[0].ends_with("flapping"), "{}", sent[0]);
+        assert!(sent[1].starts_with("<12>"), "{}", sent[1]);
+        assert!(
+            sent[1].ends_with("last message repeated 2 times"),
+            "{}",
+            sent[1]
+        );
+        assert!(sent[2].starts_with("<11>"), "{}", sent[2]);
+        assert!(sent[3].contains(" check - flapping"), "{}", sent[3]);
+        assert!(sent[4].starts_with("<11>1 "), "{}", sent[4]);
+        assert!(
+            sent[4].contains(" check - last message repeated 1 times"),
+            "{}",
+            sent[4]
+        );
+    }
+
+    #[test]
+    fn test_report_after_failure() {
+        let recorder = Recorder::default();
+        let mut sender = DedupSender::new(recorder.clone());
+
+        for _ in 0..3 {
+            sender.send_rfc3164(Severity::WARNING, "flapping").unwrap();
+        }
+        recorder.set_down(true);
+        sender.flush().unwrap_err();
+        sender.send_rfc3164(Severity::ERROR, "other").unwrap_err();
+        recorder.set_down(false);
+        sender.send_rfc3164(Severity::WARNING, "flapping").unwrap();
+        sender.flush().unwrap();
+
+        let sent = recorder.sent();
+        assert_eq!(sent.len(), 2);
+        assert!(sent[0].ends_with("flapping"), "{}", sent[0]);
+        assert!(
+            sent[1].ends_with("last message repeated 3 times"),
+            "{}",
+            sent[1]
+        );
+    }
+}
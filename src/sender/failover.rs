@@ -0,0 +1,228 @@
+// Copyright 2024 FastLabs Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::io;
+use std::time::Duration;
+use std::time::Instant;
+
+use crate::format::SyslogContext;
+use crate::sender::Sender;
+use crate::sender::SyslogSender;
+
+/// A sender that sends messages to the first healthy destination of an ordered list.
+///
+/// Messages are sent to the active destination, initially the first one. When sending fails, the
+/// next destinations are tried in order, and the first one that succeeds becomes active. While a
+/// destination other than the primary is active, the preceding destinations are periodically
+/// tried again, so that messages fail back to the primary once it recovers. This is similar to
+/// rsyslog's `action.execOnlyWhenPreviousIsSuspended`.
+///
+/// Stream senders cannot recover from a broken connection on their own, so they should be wrapped
+/// in a [`ReconnectingSender`](crate::sender::ReconnectingSender) to be able to fail back.
+///
+/// # Example
+///
+/// ```rust, no_run
+/// use fasyslog::sender::FailoverSender;
+/// use fasyslog::sender::Sender;
+/// use fasyslog::sender::SyslogSender;
+///
+/// let mut sender = FailoverSender::new(vec![
+///     SyslogSender::Tcp(fasyslog::sender::tcp("primary.example.com:601").unwrap()),
+///     SyslogSender::Tcp(fasyslog::sender::tcp("secondary.example.com:601").unwrap()),
+///     fasyslog::sender::unix_well_known().unwrap(),
+/// ]);
+/// sender
+///     .send_rfc3164(fasyslog::Severity::INFORMATIONAL, "Hello, syslog!")
+///     .unwrap();
+/// sender.flush().unwrap();
+/// ```
+#[derive(Debug)]
+pub struct FailoverSender {
+    senders: Vec<SyslogSender>,
+    // whether each destination has been sent messages since it was last flushed
+    unflushed: Vec<bool>,
+    active: usize,
+    context: SyslogContext,
+    failback_interval: Duration,
+    next_failback: Option<Instant>,
+}
+
+impl FailoverSender {
+    /// Create a new failover sender over the given destinations, in order of preference.
+    ///
+    /// The context is copied from the first destination, if any.
+    pub fn new(senders: Vec<SyslogSender>) -> Self {
+        let context = match senders.first() {
+            Some(sender) => sender.context().clone(),
+            None => SyslogContext::default(),
+        };
+        Self {
+            unflushed: vec![false; senders.len()],
+            senders,
+            active: 0,
+            context,
+            failback_interval: Duration::from_secs(30),
+            next_failback: None,
+        }
+    }
+
+    /// Set the context when formatting Syslog message.
+    pub fn set_context(&mut self, context: SyslogContext) {
+        self.context = context;
+    }
+
+    /// Set the interval between attempts to fail back to the preceding destinations.
+    ///
+    /// Default is 30 seconds.
+    pub fn set_failback_interval(&mut self, failback_interval: Duration) {
+        self.failback_interval = failback_interval;
+    }
+
+    /// Returns the index of the active destination.
+    pub fn active(&self) -> usize {
+        self.active
+    }
+
+    /// Returns the destinations.
+    pub fn senders(&self) -> &[SyslogSender] {
+        &self.senders
+    }
+
+    /// Mutate the destinations.
+    pub fn senders_mut(&mut self) -> &mut [SyslogSender] {
+        &mut self.senders
+    }
+
+    fn activate(&mut self, index: usize) {
+        if index != self.active {
+            self.active = index;
+            self.next_failback = (index > 0).then(|| Instant::now() + self.failback_interval);
+        }
+    }
+
+    fn failback_due(&self) -> bool {
+        match self.next_failback {
+            Some(next_failback) => self.active > 0 && Instant::now() >= next_failback,
+            None => false,
+        }
+    }
+}
+
+impl Sender for FailoverSender {
+    fn send_formatted(&mut self, formatted: &[u8]) -> io::Result<()> {
+        let len = self.senders.len();
+        // Try the preceding destinations first when it is time to fail back, and then every
+        // destination starting from the active one.
+        let start = if self.failback_due() {
+            self.next_failback = Some(Instant::now() + self.failback_interval);
+            0
+        } else {
+            self.active
+        };
+        let mut error = None;
+        for index in (start..len).chain(0..start) {
+            match self.senders[index].send_formatted(formatted) {
+                Ok(()) => {
+                    self.unflushed[index] = true;
+                    self.activate(index);
+                    return Ok(());
+                }
+                Err(err) => error = Some(err),
+            }
+        }
+        Err(error.unwrap_or_else(|| {
+            io::Error::new(io::ErrorKind::NotConnected, "no destination to send to")
+        }))
+    }
+
+    /// Flush the active destination, and every other destination that has been sent messages
+    /// since it was last flushed, such as the one that was active before a failover or failback.
+    ///
+    /// If flushing the active destination fails, the next destination becomes active. The first
+    /// error is returned, since the messages buffered by a failed destination may have been lost.
+    fn flush(&mut self) -> io::Result<()> {
+        let mut result = Ok(());
+        for index in 0..self.senders.len() {
+            let unflushed = std::mem::take(&mut self.unflushed[index]);
+            if !unflushed && index != self.active {
+                continue;
+            }
+            if let Err(err) = self.senders[index].flush() {
+                if index == self.active {
+                    let next = (self.active + 1) % self.senders.len();
+                    self.activate(next);
+                }
+                if result.is_ok() {
+                    result = Err(err);
+                }
+            }
+        }
+        result
+    }
+
+    fn context(&self) -> &SyslogContext {
+        &self.context
+    }
+
+    fn context_mut(&mut self) -> &mut SyslogContext {
+        &mut self.context
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sender::testing::Recorder;
+
+    #[test]
+    fn test_failover_and_failback() {
+        let primary = Recorder::default();
+        let secondary = Recorder::default();
+        let mut sender = FailoverSender::new(vec![primary.to_sender(), secondary.to_sender()]);
+        sender.set_failback_interval(Duration::ZERO);
+
+        sender.send_formatted(b"1").unwrap();
+        primary.set_down(true);
+        sender.send_formatted(b"2").unwrap();
+        assert_eq!(sender.active(), 1);
+        primary.set_down(false);
+        sender.send_formatted(b"3").unwrap();
+        assert_eq!(sender.active(), 0);
+
+        assert_eq!(primary.sent(), ["1", "3"]);
+        assert_eq!(secondary.sent(), ["2"]);
+    }
+
+    #[test]
+    fn test_flush_after_failback() {
+        let primary = Recorder::buffered();
+        let secondary = Recorder::buffered();
+        let mut sender = FailoverSender::new(vec![primary.to_sender(), secondary.to_sender()]);
+        sender.set_failback_interval(Duration::ZERO);
+
+        primary.set_down(true);
+        sender.send_formatted(b"1").unwrap();
+        assert_eq!(sender.active(), 1);
+        primary.set_down(false);
+        sender.send_formatted(b"2").unwrap();
+        assert_eq!(sender.active(), 0);
+
+        // the secondary is no longer active, but still buffers the message sent before failback
+        assert!(secondary.sent().is_empty());
+        sender.flush().unwrap();
+        assert_eq!(primary.sent(), ["2"]);
+        assert_eq!(secondary.sent(), ["1"]);
+    }
+}
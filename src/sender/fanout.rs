@@ -0,0 +1,426 @@
+// Copyright 2024 FastLabs Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::fmt;
+use std::io;
+use std::sync::Arc;
+
+use crate::format::SyslogContext;
+use crate::format::SyslogFormat;
+use crate::sender::Sender;
+use crate::sender::SyslogSender;
+use crate::Facility;
+use crate::Selector;
+use crate::Severity;
+
+/// A destination of a [`FanoutSender`], with its own filters and format.
+#[derive(Debug)]
+pub struct FanoutDestination {
+    sender: SyslogSender,
+    min_severity: Severity,
+    facilities: Option<Vec<Facility>>,
+    selector: Option<Selector>,
+    format: Option<Arc<dyn SyslogFormat>>,
+}
+
+impl FanoutDestination {
+    /// Create a new destination that accepts every message.
+    pub fn new(sender: SyslogSender) -> Self {
+        Self {
+            sender,
+            min_severity: Severity::DEBUG,
+            facilities: None,
+            selector: None,
+            format: None,
+        }
+    }
+
+    /// Only send messages at least as severe as the given severity.
+    ///
+    /// Default is [`Severity::DEBUG`], which accepts every message.
+    pub fn with_min_severity(mut self, min_severity: Severity) -> Self {
+        self.min_severity = min_severity;
+        self
+    }
+
+    /// Only send messages of the given facilities.
+    ///
+    /// The facility of a message is the facility of the [`FanoutSender`]'s context. By default,
+    /// messages of all facilities are sent.
+    pub fn with_facilities(mut self, facilities: impl IntoIterator<Item = Facility>) -> Self {
+        self.facilities = Some(facilities.into_iter().collect());
+        self
+    }
+
+    /// Only send messages selected by the given [syslog.conf(5)] selector.
+    ///
+    /// The facility of a message is the facility of the [`FanoutSender`]'s context.
+    ///
+    /// [syslog.conf(5)]: https://man7.org/linux/man-pages/man5/syslog.conf.5.html
+    pub fn with_selector(mut self, selector: Selector) -> Self {
+        self.selector = Some(selector);
+        self
+    }
+
+    /// Format messages sent to this destination with the given format, instead of the format
+    /// given by the caller.
+    pub fn with_format(mut self, format: impl SyslogFormat + 'static) -> Self {
+        self.format = Some(Arc::new(format));
+        self
+    }
+
+    /// Returns the wrapped sender.
+    pub fn sender(&self) -> &SyslogSender {
+        &self.sender
+    }
+
+    /// Mutate the wrapped sender.
+    pub fn sender_mut(&mut self) -> &mut SyslogSender {
+        &mut self.sender
+    }
+
+    fn accepts(&self, facility: Facility, severity: Severity) -> bool {
+        severity <= self.min_severity
+            && match &self.facilities {
+                Some(facilities) => facilities.contains(&facility),
+                None => true,
+            }
+            && match &self.selector {
+                Some(selector) => selector.matches(facility, severity),
+                None => true,
+            }
+    }
+}
+
+/// A sender that sends every message to several destinations.
+///
+/// Each [`FanoutDestination`] can filter messages by severity and facility, and override the
+/// format of the messages sent to it. Destinations can also be added with syslog.conf rules by
+/// [`route`](FanoutSender::route), to route messages to different senders.
+///
+/// A failure of one destination does not prevent sending to the others. If any destination
+/// fails, the returned error wraps a [`FanoutError`] with the errors of all failed destinations.
+///
+/// # Example
+///
+/// ```rust, no_run
+/// use fasyslog::format::RFC5424Format;
+/// use fasyslog::sender::FanoutDestination;
+/// use fasyslog::sender::FanoutSender;
+/// use fasyslog::sender::Sender;
+/// use fasyslog::sender::SyslogSender;
+/// use fasyslog::Severity;
+///
+/// let mut sender = FanoutSender::new(vec![
+///     FanoutDestination::new(fasyslog::sender::unix_well_known().unwrap()),
+///     FanoutDestination::new(SyslogSender::Tcp(
+///         fasyslog::sender::tcp("siem.example.com:601").unwrap(),
+///     ))
+///     .with_min_severity(Severity::WARNING)
+///     .with_format(RFC5424Format::default()),
+/// ]);
+/// sender
+///     .send_rfc3164(Severity::ERROR, "Hello, syslog!")
+///     .unwrap();
+/// sender.flush().unwrap();
+/// ```
+#[derive(Debug)]
+pub struct FanoutSender {
+    destinations: Vec<FanoutDestination>,
+    context: SyslogContext,
+}
+
+impl FanoutSender {
+    /// Create a new fan-out sender over the given destinations.
+    ///
+    /// Messages are formatted with a context copied from the first destination, if any. They are
+    /// formatted once for all the destinations without their own
+    /// [format](FanoutDestination::with_format), and once more for each destination with one.
+    pub fn new(destinations: Vec<FanoutDestination>) -> Self {
+        let context = match destinations.first() {
+            Some(destination) => destination.sender.context().clone(),
+            None => SyslogContext::default(),
+        };
+        Self {
+            destinations,
+            context,
+        }
+    }
+
+    /// Set the context when formatting Syslog message.
+    pub fn set_context(&mut self, context: SyslogContext) {
+        self.context = context;
+    }
+
+    /// Add a destination.
+    pub fn push(&mut self, destination: FanoutDestination) {
+        self.destinations.push(destination);
+    }
+
+    /// Add a destination that receives the messages selected by the given rule, written in the
+    /// selector syntax of [syslog.conf(5)], such as `*.info;mail.none`.
+    ///
+    /// As in syslog.conf, a message is sent to every destination whose rule selects it. See
+    /// [`Selector`] for the syntax.
+    ///
+    /// [syslog.conf(5)]: https://man7.org/linux/man-pages/man5/syslog.conf.5.html
+    pub fn route(&mut self, selector: &str, sender: SyslogSender) -> io::Result<()> {
+        let selector = Selector::parse(selector)?;
+        self.push(FanoutDestination::new(sender).with_selector(selector));
+        Ok(())
+    }
+
+    /// Returns the destinations.
+    pub fn destinations(&self) -> &[FanoutDestination] {
+        &self.destinations
+    }
+
+    /// Mutate the destinations.
+    pub fn destinations_mut(&mut self) -> &mut [FanoutDestination] {
+        &mut self.destinations
+    }
+}
+
+impl Sender for FanoutSender {
+    /// Send the formatted message to all destinations, regardless of their filters.
+    fn send_formatted(&mut self, formatted: &[u8]) -> io::Result<()> {
+        let mut errors = Vec::new();
+        for (index, destination) in self.destinations.iter_mut().enumerate() {
+            if let Err(err) = destination.sender.send_formatted(formatted) {
+                errors.push((index, err));
+            }
+        }
+        FanoutError::check(errors)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        let mut errors = Vec::new();
+        for (index, destination) in self.destinations.iter_mut().enumerate() {
+            if let Err(err) = destination.sender.flush() {
+                errors.push((index, err));
+            }
+        }
+        FanoutError::check(errors)
+    }
+
+    fn context(&self) -> &SyslogContext {
+        &self.context
+    }
+
+    fn context_mut(&mut self) -> &mut SyslogContext {
+        &mut self.context
+    }
+
+    fn send_message(
+        &mut self,
+        format: &dyn SyslogFormat,
+        severity: Severity,
+        message: &dyn fmt::Display,
+    ) -> io::Result<()> {
+        if !self.context.enabled(severity) {
+            return Ok(());
+        }
+        let facility = self.context.get_facility();
+        let message = message.to_string();
+        let mut errors = Vec::new();
+        // formatted on first use, and shared by the destinations without their own format
+        let mut shared = None;
+        for (index, destination) in self.destinations.iter_mut().enumerate() {
+            if !destination.accepts(facility, severity) {
+                continue;
+            }
+            let result = match destination.format.as_deref() {
+                Some(format) => self
+                    .context
+                    .format_message(format, severity, &message)
+                    .and_then(|records| send_records(&mut destination.sender, &records)),
+                None => {
+                    let records = shared.get_or_insert_with(|| {
+                        self.context.format_message(format, severity, &message)
+                    });
+                    match records {
+                        Ok(records) => send_records(&mut destination.sender, records),
+                        Err(err) => Err(io::Error::new(err.kind(), err.to_string())),
+                    }
+                }
+            };
+            if let Err(err) = result {
+                errors.push((index, err));
+            }
+        }
+        FanoutError::check(errors)
+    }
+}
+
+fn send_records(sender: &mut SyslogSender, records: &[Vec<u8>]) -> io::Result<()> {
+    records
+        .iter()
+        .try_for_each(|formatted| sender.send_formatted(formatted))
+}
+
+/// The errors of the destinations that failed in a [`FanoutSender`] operation.
+///
+/// This is returned as the inner error of the [`io::Error`], and can be obtained with
+/// [`io::Error::get_ref`] and `downcast_ref`.
+#[derive(Debug)]
+pub struct FanoutError {
+    errors: Vec<(usize, io::Error)>,
+}
+
+impl FanoutError {
+    /// Returns the index of each failed destination along with its error.
+    pub fn errors(&self) -> &[(usize, io::Error)] {
+        &self.errors
+    }
+
+    fn check(errors: Vec<(usize, io::Error)>) -> io::Result<()> {
+        if errors.is_empty() {
+            return Ok(());
+        }
+        // keep the kind when all destinations failed for the same reason
+        let kind = errors[0].1.kind();
+        let kind = if errors.iter().all(|(_, err)| err.kind() == kind) {
+            kind
+        } else {
+            io::ErrorKind::Other
+        };
+        Err(io::Error::new(kind, FanoutError { errors }))
+    }
+}
+
+impl fmt::Display for FanoutError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "failed to send to {} destination(s)", self.errors.len())?;
+        for (index, err) in &self.errors {
+            write!(f, "; destination {index}: {err}")?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for FanoutError {}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::AtomicUsize;
+    use std::sync::atomic::Ordering;
+
+    use super::*;
+    use crate::format::RFC3164Format;
+    use crate::sender::testing::Recorder;
+
+    #[test]
+    fn test_fanout_filters_and_errors() {
+        let local = Recorder::default();
+        let remote = Recorder::default();
+        let broken = Recorder::default();
+        broken.set_down(true);
+        let mut sender = FanoutSender::new(vec![
+            FanoutDestination::new(broken.to_sender()).with_facilities([Facility::AUTH]),
+            FanoutDestination::new(local.to_sender()),
+            FanoutDestination::new(remote.to_sender())
+                .with_min_severity(Severity::WARNING)
+                .with_format(RFC3164Format),
+        ]);
+        let mut context = SyslogContext::default();
+        context
+            .format(crate::format::LocalFormat)
+            .appname("app")
+            .procid("1");
+        sender.set_context(context);
+
+        sender.send(Severity::INFORMATIONAL, "info").unwrap();
+        sender.send(Severity::ERROR, "error").unwrap();
+        sender.context_mut().facility(Facility::AUTH);
+        let err = sender.send(Severity::ERROR, "auth").unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::ConnectionRefused);
+        let fanout = err.get_ref().unwrap().downcast_ref::<FanoutError>();
+        assert_eq!(fanout.unwrap().errors()[0].0, 0);
+
+        let local_sent = local.sent();
+        let remote_sent = remote.sent();
+        assert_eq!(local_sent.len(), 3);
+        assert!(local_sent[0].ends_with("app[1]: info"), "{}", local_sent[0]);
+        assert_eq!(remote_sent.len(), 2);
+        assert!(remote_sent[0].starts_with("<11>"), "{}", remote_sent[0]);
+        assert!(
+            remote_sent[0].ends_with("app[1]: error"),
+            "{}",
+            remote_sent[0]
+        );
+    }
+
+    #[test]
+    fn test_format_once() {
+        #[derive(Debug, Default)]
+        struct Counting(Arc<AtomicUsize>);
+
+        impl SyslogFormat for Counting {
+            fn format(
+                &self,
+                context: &SyslogContext,
+                severity: Severity,
+                message: &dyn fmt::Display,
+                output: &mut Vec<u8>,
+            ) -> io::Result<()> {
+                self.0.fetch_add(1, Ordering::SeqCst);
+                RFC3164Format.format(context, severity, message, output)
+            }
+        }
+
+        let recorders = [
+            Recorder::default(),
+            Recorder::default(),
+            Recorder::default(),
+        ];
+        let own = Counting::default();
+        let own_count = own.0.clone();
+        let mut sender = FanoutSender::new(vec![
+            FanoutDestination::new(recorders[0].to_sender()),
+            FanoutDestination::new(recorders[1].to_sender()).with_format(own),
+            FanoutDestination::new(recorders[2].to_sender()),
+        ]);
+        let shared = Counting::default();
+        sender.send_with(&shared, Severity::ERROR, "hello").unwrap();
+
+        assert_eq!(shared.0.load(Ordering::SeqCst), 1);
+        assert_eq!(own_count.load(Ordering::SeqCst), 1);
+        for recorder in &recorders {
+            assert_eq!(recorder.sent().len(), 1);
+            assert!(recorder.sent()[0].ends_with(": hello"));
+        }
+    }
+
+    #[test]
+    fn test_route_by_selector() {
+        let messages = Recorder::default();
+        let mail = Recorder::default();
+        let mut sender = FanoutSender::new(Vec::new());
+        sender
+            .route("*.info;mail.none", messages.to_sender())
+            .unwrap();
+        sender.route("mail.*", mail.to_sender()).unwrap();
+
+        sender.send_rfc3164(Severity::DEBUG, "debug").unwrap();
+        sender.send_rfc3164(Severity::NOTICE, "notice").unwrap();
+        sender.context_mut().facility(Facility::MAIL);
+        sender.send_rfc3164(Severity::DEBUG, "mail").unwrap();
+
+        let (messages_sent, mail_sent) = (messages.sent(), mail.sent());
+        assert_eq!(messages_sent.len(), 1);
+        assert!(messages_sent[0].ends_with("notice"));
+        assert_eq!(mail_sent.len(), 1);
+        assert!(mail_sent[0].ends_with("mail"));
+    }
+}
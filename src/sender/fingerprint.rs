@@ -0,0 +1,145 @@
+// Copyright 2024 FastLabs Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::fmt;
+use std::io;
+use std::str::FromStr;
+
+use sha1::Sha1;
+use sha2::Digest;
+use sha2::Sha256;
+
+/// A hash algorithm of certificate fingerprints.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum FingerprintAlgorithm {
+    /// SHA-1, which [RFC-5425] §5.2 requires implementations to support.
+    ///
+    /// [RFC-5425]: https://datatracker.ietf.org/doc/html/rfc5425#section-5.2
+    Sha1,
+    /// SHA-256.
+    Sha256,
+}
+
+impl FingerprintAlgorithm {
+    /// Returns the name of the algorithm in the IANA "Hash Function Textual Names" registry.
+    pub fn name(self) -> &'static str {
+        match self {
+            FingerprintAlgorithm::Sha1 => "SHA-1",
+            FingerprintAlgorithm::Sha256 => "SHA-256",
+        }
+    }
+
+    fn digest(self, der: &[u8]) -> Vec<u8> {
+        match self {
+            FingerprintAlgorithm::Sha1 => Sha1::digest(der).to_vec(),
+            FingerprintAlgorithm::Sha256 => Sha256::digest(der).to_vec(),
+        }
+    }
+}
+
+/// A certificate fingerprint as defined in [RFC-5425] §4.2.2.
+///
+/// Fingerprints are written as the name of the hash algorithm followed by the colon-separated
+/// hexadecimal digest of the DER-encoded certificate, such as `SHA-256:E3:B0:C4:…`.
+///
+/// [RFC-5425]: https://datatracker.ietf.org/doc/html/rfc5425#section-4.2.2
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Fingerprint {
+    algorithm: FingerprintAlgorithm,
+    digest: Vec<u8>,
+}
+
+impl Fingerprint {
+    /// Compute the fingerprint of a DER-encoded certificate.
+    pub fn of_der(algorithm: FingerprintAlgorithm, der: &[u8]) -> Self {
+        Self {
+            algorithm,
+            digest: algorithm.digest(der),
+        }
+    }
+
+    /// Returns the hash algorithm of the fingerprint.
+    pub fn algorithm(&self) -> FingerprintAlgorithm {
+        self.algorithm
+    }
+
+    /// Returns `true` if the fingerprint is the one of the given DER-encoded certificate.
+    pub fn matches(&self, der: &[u8]) -> bool {
+        self.algorithm.digest(der) == self.digest
+    }
+}
+
+impl FromStr for Fingerprint {
+    type Err = io::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || {
+            let message = format!("invalid certificate fingerprint: {s}");
+            io::Error::new(io::ErrorKind::InvalidInput, message)
+        };
+        let (name, digest) = s.trim().split_once(':').ok_or_else(invalid)?;
+        let (algorithm, len) = match &name.to_uppercase()[..] {
+            "SHA-1" | "SHA1" => (FingerprintAlgorithm::Sha1, 20),
+            "SHA-256" | "SHA256" => (FingerprintAlgorithm::Sha256, 32),
+            _ => return Err(invalid()),
+        };
+        let digest = digest
+            .split(':')
+            .map(|byte| match byte.len() {
+                2 => u8::from_str_radix(byte, 16).ok(),
+                _ => None,
+            })
+            .collect::<Option<Vec<_>>>()
+            .filter(|digest| digest.len() == len)
+            .ok_or_else(invalid)?;
+        Ok(Self { algorithm, digest })
+    }
+}
+
+impl fmt::Display for Fingerprint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.algorithm.name())?;
+        for byte in &self.digest {
+            write!(f, ":{byte:02X}")?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_fingerprint() {
+        let sha256 = "SHA-256:E3:B0:C4:42:98:FC:1C:14:9A:FB:F4:C8:99:6F:B9:24:\
+            27:AE:41:E4:64:9B:93:4C:A4:95:99:1B:78:52:B8:55";
+        let fingerprint = sha256.parse::<Fingerprint>().unwrap();
+        assert_eq!(fingerprint.algorithm(), FingerprintAlgorithm::Sha256);
+        assert!(fingerprint.matches(b""));
+        assert!(!fingerprint.matches(b"certificate"));
+        assert_eq!(fingerprint.to_string(), sha256);
+
+        let sha1 = "sha-1:da:39:a3:ee:5e:6b:4b:0d:32:55:bf:ef:95:60:18:90:af:d8:07:09";
+        let fingerprint = sha1.parse::<Fingerprint>().unwrap();
+        assert_eq!(
+            fingerprint,
+            Fingerprint::of_der(FingerprintAlgorithm::Sha1, b"")
+        );
+
+        assert!("SHA-256:E3:B0".parse::<Fingerprint>().is_err());
+        assert!("MD5:D4:1D".parse::<Fingerprint>().is_err());
+        assert!("E3B0C442".parse::<Fingerprint>().is_err());
+    }
+}
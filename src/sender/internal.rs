@@ -12,56 +12,70 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-macro_rules! impl_syslog_sender_common {
-    ($sender:ident) => {
-        impl $sender {
-            /// Send a message with the given severity as defined in RFC-3164.
-            pub fn send_rfc3164<M: std::fmt::Display>(
-                &mut self,
-                severity: $crate::Severity,
-                message: M,
-            ) -> std::io::Result<()> {
-                let message = self.context.format_rfc3164(severity, Some(message));
-                self.send_formatted(message.to_string().as_bytes())
-            }
+use std::io;
 
-            /// Send a message with the given severity as defined in RFC-5424.
-            pub fn send_rfc5424<S: Into<String>, M: std::fmt::Display>(
-                &mut self,
-                severity: $crate::Severity,
-                msgid: Option<S>,
-                elements: Vec<$crate::SDElement>,
-                message: M,
-            ) -> std::io::Result<()> {
-                let message = self
-                    .context
-                    .format_rfc5424(severity, msgid, elements, Some(message));
-                self.send_formatted(message.to_string().as_bytes())
-            }
+/// Report an expired write timeout as [`io::ErrorKind::TimedOut`], and mark the stream as
+/// poisoned.
+///
+/// Blocking sockets report expired timeouts as [`io::ErrorKind::WouldBlock`] on some platforms.
+/// Part of a message may have been written when the timeout expired, so the stream can no longer
+/// be framed correctly, and TLS streams refuse to retry the write anyway.
+pub(crate) fn write_failed(err: io::Error, poisoned: &mut bool) -> io::Error {
+    match err.kind() {
+        io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut => {
+            *poisoned = true;
+            io::Error::new(io::ErrorKind::TimedOut, "write timed out")
         }
-    };
+        _ => err,
+    }
 }
 
-pub(crate) use impl_syslog_sender_common;
+/// Fail with [`io::ErrorKind::BrokenPipe`] if a previous write timed out.
+pub(crate) fn check_poisoned(poisoned: bool) -> io::Result<()> {
+    if poisoned {
+        let message = "connection is unusable after a write timeout";
+        return Err(io::Error::new(io::ErrorKind::BrokenPipe, message));
+    }
+    Ok(())
+}
 
-macro_rules! impl_syslog_stream_send_formatted {
+macro_rules! impl_syslog_stream_sender {
     ($sender:ident) => {
-        impl $sender {
-            /// Send a formatted message to the stream.
-            pub fn send_formatted(&mut self, message: &[u8]) -> std::io::Result<()> {
+        impl $crate::sender::Sender for $sender {
+            fn send_formatted(&mut self, formatted: &[u8]) -> std::io::Result<()> {
                 use std::io::Write;
-                self.writer.write_all(message)?;
-                self.writer.write_all(self.postfix.as_bytes())?;
-                Ok(())
+                $crate::sender::internal::check_poisoned(self.poisoned)?;
+                let result = match self.framing {
+                    $crate::sender::Framing::OctetCounting => {
+                        write!(self.writer, "{} ", formatted.len())
+                            .and_then(|()| self.writer.write_all(formatted))
+                    }
+                    $crate::sender::Framing::NonTransparent => self
+                        .writer
+                        .write_all(formatted)
+                        .and_then(|()| self.writer.write_all(self.postfix.as_bytes())),
+                };
+                result
+                    .map_err(|err| $crate::sender::internal::write_failed(err, &mut self.poisoned))
             }
 
-            /// Flush the stream.
-            pub fn flush(&mut self) -> std::io::Result<()> {
+            fn flush(&mut self) -> std::io::Result<()> {
                 use std::io::Write;
-                self.writer.flush()
+                $crate::sender::internal::check_poisoned(self.poisoned)?;
+                self.writer
+                    .flush()
+                    .map_err(|err| $crate::sender::internal::write_failed(err, &mut self.poisoned))
+            }
+
+            fn context(&self) -> &$crate::format::SyslogContext {
+                &self.context
+            }
+
+            fn context_mut(&mut self) -> &mut $crate::format::SyslogContext {
+                &mut self.context
             }
         }
     };
 }
 
-pub(crate) use impl_syslog_stream_send_formatted;
+pub(crate) use impl_syslog_stream_sender;
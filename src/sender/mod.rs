@@ -17,6 +17,10 @@
 use std::fmt;
 use std::io;
 
+use crate::format::RFC3164Format;
+use crate::format::RFC5424Format;
+use crate::format::SyslogContext;
+use crate::format::SyslogFormat;
 use crate::SDElement;
 use crate::Severity;
 
@@ -30,14 +34,241 @@ mod native_tls;
 #[cfg(feature = "native-tls")]
 pub use native_tls::*;
 
+#[cfg(feature = "native-tls")]
+mod fingerprint;
+#[cfg(feature = "native-tls")]
+pub use fingerprint::*;
+
+#[cfg(feature = "rustls")]
+mod rustls;
+#[cfg(feature = "rustls")]
+pub use rustls::*;
+
+mod stdio;
+pub use stdio::*;
+
+mod batch;
+pub use batch::*;
+
+mod dedup;
+pub use dedup::*;
+
+mod failover;
+pub use failover::*;
+
+mod fanout;
+pub use fanout::*;
+
+mod queue;
+pub use queue::*;
+
+mod ratelimit;
+pub use ratelimit::*;
+
+mod reconnect;
+pub use reconnect::*;
+
+mod shared;
+pub use shared::*;
+
+mod socket;
+pub use socket::*;
+
+mod spool;
+pub use spool::*;
+
+#[cfg(feature = "tokio")]
+pub mod tokio;
+
+#[cfg(feature = "serde")]
+mod config;
+#[cfg(feature = "serde")]
+pub use config::*;
+
 mod tcp;
 pub use tcp::*;
 
 mod udp;
 pub use udp::*;
 
+mod url;
+pub use url::*;
+
 pub(crate) mod internal;
 
+#[cfg(test)]
+pub(crate) mod testing;
+
+/// Framing of messages sent over a stream, as defined in [RFC-6587] §3.4.
+///
+/// [RFC-6587]: https://datatracker.ietf.org/doc/html/rfc6587#section-3.4
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Deserialize),
+    serde(rename_all = "kebab-case")
+)]
+pub enum Framing {
+    /// Every message is prefixed with its length in octets and a space (RFC-6587 §3.4.1).
+    ///
+    /// This is the framing mandated by [RFC-5425] for syslog over TLS.
+    ///
+    /// [RFC-5425]: https://datatracker.ietf.org/doc/html/rfc5425#section-4.3
+    OctetCounting,
+    /// Every message is terminated by the postfix of the sender (RFC-6587 §3.4.2).
+    #[default]
+    NonTransparent,
+}
+
+/// A syslog sender.
+///
+/// Implementors only need to transfer pre-formatted messages and expose their [`SyslogContext`];
+/// formatting is provided on top of that. Users can implement this trait for their own transports,
+/// and wrap them in [`SyslogSender::Custom`] to use them everywhere the built-in senders work.
+pub trait Sender: fmt::Debug {
+    /// Send a pre-formatted message.
+    fn send_formatted(&mut self, formatted: &[u8]) -> io::Result<()>;
+
+    /// Flush the underlying writer if needed.
+    ///
+    /// When the underlying writer is a streaming writer (TCP, UnixStream, etc.), periodically
+    /// flush is essential to ensure that the message is sent to the syslog server [1].
+    ///
+    /// When the underlying writer is a datagram writer (UDP, UnixDatagram, etc.), flush is a no-op,
+    /// and every call to `send_xxx` defines the boundary of the packet.
+    ///
+    /// [1]: https://github.com/Geal/rust-syslog/issues/69
+    fn flush(&mut self) -> io::Result<()>;
+
+    /// Returns the context when formatting Syslog message.
+    fn context(&self) -> &SyslogContext;
+
+    /// Mutate the context when formatting Syslog message.
+    fn context_mut(&mut self) -> &mut SyslogContext;
+
+    /// Send a message with the given severity in the given format.
+    ///
+    /// This is the object-safe form of [`send_with`](Sender::send_with) that all the other
+    /// `send_xxx` methods delegate to. Messages whose severity is not in [`SyslogContext::mask`]
+    /// are dropped before formatting. Multi-line messages are split as configured by
+    /// [`SyslogContext::multiline`], and formatted messages are truncated to
+    /// [`SyslogContext::max_length`].
+    fn send_message(
+        &mut self,
+        format: &dyn SyslogFormat,
+        severity: Severity,
+        message: &dyn fmt::Display,
+    ) -> io::Result<()> {
+        for formatted in self.context().format_message(format, severity, message)? {
+            self.send_formatted(&formatted)?;
+        }
+        Ok(())
+    }
+
+    /// Send a message with the given severity in the given format.
+    fn send_with<M: fmt::Display>(
+        &mut self,
+        format: &dyn SyslogFormat,
+        severity: Severity,
+        message: M,
+    ) -> io::Result<()>
+    where
+        Self: Sized,
+    {
+        self.send_message(format, severity, &message)
+    }
+
+    /// Send a message with the given severity in the default format of the context.
+    ///
+    /// See also [`SyslogContext::format`].
+    fn send<M: fmt::Display>(&mut self, severity: Severity, message: M) -> io::Result<()>
+    where
+        Self: Sized,
+    {
+        match self.context().default_format() {
+            Some(format) => self.send_message(&*format, severity, &message),
+            None => self.send_message(&RFC3164Format, severity, &message),
+        }
+    }
+
+    /// Send a message with the given severity as defined in RFC-3164.
+    fn send_rfc3164<M: fmt::Display>(&mut self, severity: Severity, message: M) -> io::Result<()>
+    where
+        Self: Sized,
+    {
+        self.send_message(&RFC3164Format, severity, &message)
+    }
+
+    /// Send a message with the given severity as defined in RFC-5424.
+    fn send_rfc5424<S: Into<String>, M: fmt::Display>(
+        &mut self,
+        severity: Severity,
+        msgid: Option<S>,
+        elements: Vec<SDElement>,
+        message: M,
+    ) -> io::Result<()>
+    where
+        Self: Sized,
+    {
+        let format = RFC5424Format::new(msgid, elements);
+        self.send_message(&format, severity, &message)
+    }
+}
+
+impl<S: Sender + ?Sized> Sender for Box<S> {
+    fn send_formatted(&mut self, formatted: &[u8]) -> io::Result<()> {
+        (**self).send_formatted(formatted)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        (**self).flush()
+    }
+
+    fn context(&self) -> &SyslogContext {
+        (**self).context()
+    }
+
+    fn context_mut(&mut self) -> &mut SyslogContext {
+        (**self).context_mut()
+    }
+
+    fn send_message(
+        &mut self,
+        format: &dyn SyslogFormat,
+        severity: Severity,
+        message: &dyn fmt::Display,
+    ) -> io::Result<()> {
+        (**self).send_message(format, severity, message)
+    }
+}
+
+impl<S: Sender + ?Sized> Sender for &mut S {
+    fn send_formatted(&mut self, formatted: &[u8]) -> io::Result<()> {
+        (**self).send_formatted(formatted)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        (**self).flush()
+    }
+
+    fn context(&self) -> &SyslogContext {
+        (**self).context()
+    }
+
+    fn context_mut(&mut self) -> &mut SyslogContext {
+        (**self).context_mut()
+    }
+
+    fn send_message(
+        &mut self,
+        format: &dyn SyslogFormat,
+        severity: Severity,
+        message: &dyn fmt::Display,
+    ) -> io::Result<()> {
+        (**self).send_message(format, severity, message)
+    }
+}
+
 /// Static dispatch for the different sender types.
 #[derive(Debug)]
 pub enum SyslogSender {
@@ -45,90 +276,196 @@ pub enum SyslogSender {
     Udp(UdpSender),
     #[cfg(feature = "native-tls")]
     NativeTlsSender(NativeTlsSender),
+    #[cfg(feature = "rustls")]
+    RustlsSender(RustlsSender),
     #[cfg(unix)]
     UnixDatagram(UnixDatagramSender),
     #[cfg(unix)]
     UnixStream(UnixStreamSender),
+    #[cfg(target_os = "linux")]
+    Journald(JournaldSender),
+    Stdio(StdioSender),
+    /// A user-defined sender.
+    Custom(Box<dyn Sender + Send>),
 }
 
 impl SyslogSender {
-    /// Send a message with the given severity as defined in RFC-3164.
-    pub fn send_rfc3164<M: fmt::Display>(
-        &mut self,
-        severity: Severity,
-        message: M,
-    ) -> io::Result<()> {
+    fn as_dyn(&self) -> &dyn Sender {
         match self {
-            SyslogSender::Tcp(sender) => sender.send_rfc3164(severity, message),
-            SyslogSender::Udp(sender) => sender.send_rfc3164(severity, message),
+            SyslogSender::Tcp(sender) => sender,
+            SyslogSender::Udp(sender) => sender,
             #[cfg(feature = "native-tls")]
-            SyslogSender::NativeTlsSender(sender) => sender.send_rfc3164(severity, message),
+            SyslogSender::NativeTlsSender(sender) => sender,
+            #[cfg(feature = "rustls")]
+            SyslogSender::RustlsSender(sender) => sender,
             #[cfg(unix)]
-            SyslogSender::UnixDatagram(sender) => sender.send_rfc3164(severity, message),
+            SyslogSender::UnixDatagram(sender) => sender,
             #[cfg(unix)]
-            SyslogSender::UnixStream(sender) => sender.send_rfc3164(severity, message),
+            SyslogSender::UnixStream(sender) => sender,
+            #[cfg(target_os = "linux")]
+            SyslogSender::Journald(sender) => sender,
+            SyslogSender::Stdio(sender) => sender,
+            SyslogSender::Custom(sender) => sender,
         }
     }
 
-    /// Send a message with the given severity as defined in RFC-5424.
-    pub fn send_rfc5424<S: Into<String>, M: fmt::Display>(
-        &mut self,
-        severity: Severity,
-        msgid: Option<S>,
-        elements: Vec<SDElement>,
-        message: M,
-    ) -> io::Result<()> {
+    fn as_dyn_mut(&mut self) -> &mut dyn Sender {
         match self {
-            SyslogSender::Tcp(sender) => sender.send_rfc5424(severity, msgid, elements, message),
-            SyslogSender::Udp(sender) => sender.send_rfc5424(severity, msgid, elements, message),
+            SyslogSender::Tcp(sender) => sender,
+            SyslogSender::Udp(sender) => sender,
             #[cfg(feature = "native-tls")]
-            SyslogSender::NativeTlsSender(sender) => {
-                sender.send_rfc5424(severity, msgid, elements, message)
-            }
+            SyslogSender::NativeTlsSender(sender) => sender,
+            #[cfg(feature = "rustls")]
+            SyslogSender::RustlsSender(sender) => sender,
             #[cfg(unix)]
-            SyslogSender::UnixDatagram(sender) => {
-                sender.send_rfc5424(severity, msgid, elements, message)
-            }
+            SyslogSender::UnixDatagram(sender) => sender,
             #[cfg(unix)]
-            SyslogSender::UnixStream(sender) => {
-                sender.send_rfc5424(severity, msgid, elements, message)
-            }
+            SyslogSender::UnixStream(sender) => sender,
+            #[cfg(target_os = "linux")]
+            SyslogSender::Journald(sender) => sender,
+            SyslogSender::Stdio(sender) => sender,
+            SyslogSender::Custom(sender) => sender,
         }
     }
+}
 
-    /// Send a pre-formatted message.
-    pub fn send_formatted(&mut self, formatted: &[u8]) -> io::Result<()> {
-        match self {
-            SyslogSender::Tcp(sender) => sender.send_formatted(formatted),
-            SyslogSender::Udp(sender) => sender.send_formatted(formatted),
-            #[cfg(feature = "native-tls")]
-            SyslogSender::NativeTlsSender(sender) => sender.send_formatted(formatted),
-            #[cfg(unix)]
-            SyslogSender::UnixDatagram(sender) => sender.send_formatted(formatted),
-            #[cfg(unix)]
-            SyslogSender::UnixStream(sender) => sender.send_formatted(formatted),
+impl Sender for SyslogSender {
+    fn send_formatted(&mut self, formatted: &[u8]) -> io::Result<()> {
+        self.as_dyn_mut().send_formatted(formatted)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.as_dyn_mut().flush()
+    }
+
+    fn context(&self) -> &SyslogContext {
+        self.as_dyn().context()
+    }
+
+    fn context_mut(&mut self) -> &mut SyslogContext {
+        self.as_dyn_mut().context_mut()
+    }
+
+    fn send_message(
+        &mut self,
+        format: &dyn SyslogFormat,
+        severity: Severity,
+        message: &dyn fmt::Display,
+    ) -> io::Result<()> {
+        self.as_dyn_mut().send_message(format, severity, message)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sender::testing::Recorder;
+    use crate::Facility;
+
+    #[derive(Debug)]
+    struct Uppercase;
+
+    impl SyslogFormat for Uppercase {
+        fn format(
+            &self,
+            context: &SyslogContext,
+            severity: Severity,
+            message: &dyn fmt::Display,
+            output: &mut Vec<u8>,
+        ) -> io::Result<()> {
+            let priority = (context.get_facility() as u8) << 3 | severity as u8;
+            let message = message.to_string().to_uppercase();
+            output.extend_from_slice(format!("{priority} {message}").as_bytes());
+            Ok(())
         }
     }
 
-    /// Flush the underlying writer if needed.
-    ///
-    /// When the underlying writer is a streaming writer (TCP, UnixStream, etc.), periodically
-    /// flush is essential to ensure that the message is sent to the syslog server [1].
-    ///
-    /// When the underlying writer is a datagram writer (UDP, UnixDatagram, etc.), flush is a no-op,
-    /// and every call to `send_xxx` defines the boundary of the packet.
-    ///
-    /// [1]: https://github.com/Geal/rust-syslog/issues/69
-    pub fn flush(&mut self) -> io::Result<()> {
-        match self {
-            SyslogSender::Tcp(sender) => sender.flush(),
-            SyslogSender::Udp(_) => Ok(()),
-            #[cfg(feature = "native-tls")]
-            SyslogSender::NativeTlsSender(sender) => sender.flush(),
-            #[cfg(unix)]
-            SyslogSender::UnixDatagram(_) => Ok(()),
-            #[cfg(unix)]
-            SyslogSender::UnixStream(sender) => sender.flush(),
+    #[test]
+    fn test_send_with_custom_format() {
+        let mut recorder = Recorder::default();
+        recorder.context_mut().facility(Facility::LOCAL0);
+        recorder
+            .clone()
+            .send_with(&Uppercase, Severity::WARNING, "hello")
+            .unwrap();
+        assert_eq!(recorder.sent(), ["132 HELLO"]);
+    }
+
+    #[test]
+    fn test_send_falls_back_to_rfc3164() {
+        let mut recorder = Recorder::default();
+        recorder.context_mut().hostname("host").appname("app");
+        assert!(recorder.context().default_format().is_none());
+        recorder.clone().send(Severity::ERROR, "hello").unwrap();
+        let sent = recorder.sent();
+        assert_eq!(sent.len(), 1);
+        // <PRI>TIMESTAMP HOSTNAME TAG: MSG
+        assert!(sent[0].starts_with("<11>"), "{}", sent[0]);
+        assert!(sent[0].contains(" host app["), "{}", sent[0]);
+        assert!(sent[0].ends_with("]: hello"), "{}", sent[0]);
+    }
+
+    /// A sender that sends the severity and the message only, by overriding `send_message`.
+    #[derive(Debug, Default)]
+    struct Bare(Recorder);
+
+    impl Sender for Bare {
+        fn send_formatted(&mut self, formatted: &[u8]) -> io::Result<()> {
+            self.0.send_formatted(formatted)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            self.0.flush()
+        }
+
+        fn context(&self) -> &SyslogContext {
+            self.0.context()
         }
+
+        fn context_mut(&mut self) -> &mut SyslogContext {
+            self.0.context_mut()
+        }
+
+        fn send_message(
+            &mut self,
+            _: &dyn SyslogFormat,
+            severity: Severity,
+            message: &dyn fmt::Display,
+        ) -> io::Result<()> {
+            let formatted = format!("{severity:?} {message}");
+            self.send_formatted(formatted.as_bytes())
+        }
+    }
+
+    #[test]
+    fn test_custom_sender() {
+        let recorder = Recorder::default();
+        let mut sender = SyslogSender::Custom(Box::new(Bare(recorder.clone())));
+        sender.send_rfc3164(Severity::ERROR, "custom").unwrap();
+        sender
+            .send_rfc5424(Severity::NOTICE, Some("ID"), vec![], "custom")
+            .unwrap();
+        sender.context_mut().appname("app");
+        sender.flush().unwrap();
+
+        let mut boxed: Box<dyn Sender> = Box::new(Bare(recorder.clone()));
+        boxed
+            .send_message(&RFC3164Format, Severity::DEBUG, &"boxed")
+            .unwrap();
+        fn send_info<S: Sender>(mut sender: S, message: &str) {
+            sender.send(Severity::INFORMATIONAL, message).unwrap();
+        }
+        send_info(&mut boxed, "borrowed");
+
+        assert_eq!(sender.context().get_appname(), Some("app"));
+        assert_eq!(
+            recorder.sent(),
+            [
+                "ERROR custom",
+                "NOTICE custom",
+                "DEBUG boxed",
+                "INFORMATIONAL borrowed"
+            ]
+        );
     }
 }
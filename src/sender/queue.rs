@@ -0,0 +1,442 @@
+// Copyright 2024 FastLabs Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::collections::VecDeque;
+use std::fmt;
+use std::io;
+use std::sync::Arc;
+use std::sync::Condvar;
+use std::sync::Mutex;
+use std::sync::MutexGuard;
+use std::sync::PoisonError;
+use std::thread;
+use std::thread::JoinHandle;
+use std::time::Duration;
+use std::time::Instant;
+
+use crate::format::SyslogContext;
+use crate::format::SyslogFormat;
+use crate::sender::Sender;
+use crate::Severity;
+
+/// What an [`AsyncQueueSender`] does with a message when its queue is full.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum OverflowPolicy {
+    /// Block the caller until the queue has room for the message.
+    #[default]
+    Block,
+    /// Drop the message being sent.
+    DropNewest,
+    /// Drop the oldest message in the queue to make room for the message being sent.
+    DropOldest,
+    /// Drop the message being sent if it is less severe than the given severity, and block
+    /// otherwise.
+    ///
+    /// Messages sent with [`Sender::send_formatted`] have no known severity and are never
+    /// dropped.
+    DropBelow(Severity),
+}
+
+/// A sender that sends messages from a dedicated worker thread.
+///
+/// Messages are formatted with the context of this wrapper on the caller's thread, and put in a
+/// bounded queue. The worker thread takes messages from the queue and sends them with the wrapped
+/// sender, so that a slow destination does not block the caller. When the queue is full, the
+/// [`OverflowPolicy`] decides which message is dropped, if any; dropped messages are counted by
+/// [`dropped`](AsyncQueueSender::dropped).
+///
+/// Since messages are sent asynchronously, errors of the wrapped sender are reported by the next
+/// [`flush`](Sender::flush), which waits until the queue is drained and the wrapped sender is
+/// flushed. Dropping the sender drains the queue as well. Both give up after the drain timeout.
+///
+/// When dropping the sender gives up, the worker thread is detached instead of joined: it keeps
+/// sending the remaining messages in the background, and exits once the queue is drained. Messages
+/// still queued when the process exits are lost.
+///
+/// # Example
+///
+/// ```rust, no_run
+/// use fasyslog::sender::AsyncQueueSender;
+/// use fasyslog::sender::OverflowPolicy;
+/// use fasyslog::sender::Sender;
+///
+/// let tcp = fasyslog::sender::tcp_well_known().unwrap();
+/// let mut sender = AsyncQueueSender::new(tcp, 1024).unwrap();
+/// sender.set_policy(OverflowPolicy::DropOldest);
+/// sender
+///     .send_rfc3164(fasyslog::Severity::INFORMATIONAL, "Hello, syslog!")
+///     .unwrap();
+/// sender.flush().unwrap();
+/// ```
+#[derive(Debug)]
+pub struct AsyncQueueSender {
+    shared: Arc<Shared>,
+    worker: Option<JoinHandle<()>>,
+    context: SyslogContext,
+    capacity: usize,
+    policy: OverflowPolicy,
+    drain_timeout: Duration,
+}
+
+impl AsyncQueueSender {
+    /// Spawn a worker thread that sends messages with the given sender, from a queue that holds
+    /// up to `capacity` messages.
+    ///
+    /// Messages are formatted with a copy of the context of `sender`.
+    pub fn new<S>(sender: S, capacity: usize) -> io::Result<Self>
+    where
+        S: Sender + Send + 'static,
+    {
+        let context = sender.context().clone();
+        let shared = Arc::new(Shared::default());
+        let worker = {
+            let shared = shared.clone();
+            thread::Builder::new()
+                .name("fasyslog-queue".to_string())
+                .spawn(move || shared.run(sender))?
+        };
+        Ok(Self {
+            shared,
+            worker: Some(worker),
+            context,
+            capacity: capacity.max(1),
+            policy: OverflowPolicy::default(),
+            drain_timeout: Duration::from_secs(5),
+        })
+    }
+
+    /// Set the context when formatting Syslog message.
+    pub fn set_context(&mut self, context: SyslogContext) {
+        self.context = context;
+    }
+
+    /// Set what to do with a message when the queue is full.
+    ///
+    /// Default is [`OverflowPolicy::Block`].
+    pub fn set_policy(&mut self, policy: OverflowPolicy) {
+        self.policy = policy;
+    }
+
+    /// Set how long flushing or dropping the sender waits for the queue to be drained.
+    ///
+    /// Default is 5 seconds.
+    pub fn set_drain_timeout(&mut self, drain_timeout: Duration) {
+        self.drain_timeout = drain_timeout;
+    }
+
+    /// Returns the number of messages dropped because the queue was full.
+    pub fn dropped(&self) -> u64 {
+        self.shared.lock().dropped
+    }
+
+    /// Returns the number of messages waiting in the queue.
+    pub fn len(&self) -> usize {
+        self.shared.lock().queue.len()
+    }
+
+    /// Returns `true` if no message is waiting in the queue.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn enqueue(&self, severity: Option<Severity>, formatted: Vec<u8>) -> io::Result<()> {
+        let mut state = self.shared.lock();
+        while state.queue.len() >= self.capacity {
+            if state.finished {
+                return Err(worker_gone());
+            }
+            match self.policy {
+                OverflowPolicy::Block => {}
+                OverflowPolicy::DropNewest => {
+                    state.dropped += 1;
+                    return Ok(());
+                }
+                OverflowPolicy::DropOldest => {
+                    state.queue.pop_front();
+                    state.dropped += 1;
+                    continue;
+                }
+                OverflowPolicy::DropBelow(min_severity) => {
+                    if severity.is_some_and(|severity| severity > min_severity) {
+                        state.dropped += 1;
+                        return Ok(());
+                    }
+                }
+            }
+            state = self.shared.wait(state);
+        }
+        if state.finished {
+            return Err(worker_gone());
+        }
+        state.queue.push_back(formatted);
+        self.shared.changed.notify_all();
+        Ok(())
+    }
+}
+
+impl Sender for AsyncQueueSender {
+    fn send_formatted(&mut self, formatted: &[u8]) -> io::Result<()> {
+        self.enqueue(None, formatted.to_vec())
+    }
+
+    /// Wait until the queue is drained and the wrapped sender is flushed.
+    ///
+    /// Returns the first error of the wrapped sender since the last flush, or
+    /// [`io::ErrorKind::TimedOut`] if the queue is not drained within the drain timeout.
+    fn flush(&mut self) -> io::Result<()> {
+        let deadline = Instant::now() + self.drain_timeout;
+        let mut state = self.shared.lock();
+        state.flush_requested += 1;
+        let target = state.flush_requested;
+        self.shared.changed.notify_all();
+        while state.flushed < target {
+            if state.finished {
+                return Err(worker_gone());
+            }
+            let timeout = deadline.saturating_duration_since(Instant::now());
+            if timeout.is_zero() {
+                return Err(io::Error::new(
+                    io::ErrorKind::TimedOut,
+                    "timed out draining the queue",
+                ));
+            }
+            state = self.shared.wait_timeout(state, timeout);
+        }
+        match state.error.take() {
+            Some(err) => Err(err),
+            None => Ok(()),
+        }
+    }
+
+    fn context(&self) -> &SyslogContext {
+        &self.context
+    }
+
+    fn context_mut(&mut self) -> &mut SyslogContext {
+        &mut self.context
+    }
+
+    fn send_message(
+        &mut self,
+        format: &dyn SyslogFormat,
+        severity: Severity,
+        message: &dyn fmt::Display,
+    ) -> io::Result<()> {
+        for formatted in self.context.format_message(format, severity, message)? {
+            self.enqueue(Some(severity), formatted)?;
+        }
+        Ok(())
+    }
+}
+
+impl Drop for AsyncQueueSender {
+    fn drop(&mut self) {
+        let deadline = Instant::now() + self.drain_timeout;
+        let mut state = self.shared.lock();
+        state.flush_requested += 1;
+        state.closed = true;
+        self.shared.changed.notify_all();
+        while !state.finished {
+            let timeout = deadline.saturating_duration_since(Instant::now());
+            if timeout.is_zero() {
+                // detach the worker; it exits on its own once the queue is drained
+                return;
+            }
+            state = self.shared.wait_timeout(state, timeout);
+        }
+        drop(state);
+        if let Some(worker) = self.worker.take() {
+            let _ = worker.join();
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct Shared {
+    state: Mutex<State>,
+    changed: Condvar,
+}
+
+#[derive(Debug, Default)]
+struct State {
+    queue: VecDeque<Vec<u8>>,
+    dropped: u64,
+    error: Option<io::Error>,
+    flush_requested: u64,
+    flushed: u64,
+    closed: bool,
+    finished: bool,
+}
+
+impl Shared {
+    fn lock(&self) -> MutexGuard<'_, State> {
+        self.state.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn wait<'a>(&self, state: MutexGuard<'a, State>) -> MutexGuard<'a, State> {
+        self.changed
+            .wait(state)
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn wait_timeout<'a>(
+        &self,
+        state: MutexGuard<'a, State>,
+        timeout: Duration,
+    ) -> MutexGuard<'a, State> {
+        match self.changed.wait_timeout(state, timeout) {
+            Ok((state, _)) => state,
+            Err(err) => err.into_inner().0,
+        }
+    }
+
+    fn run(&self, mut sender: impl Sender) {
+        let _finish = Finish(self);
+        let mut state = self.lock();
+        loop {
+            if let Some(formatted) = state.queue.pop_front() {
+                self.changed.notify_all();
+                drop(state);
+                let result = sender.send_formatted(&formatted);
+                state = self.lock();
+                state.record(result);
+            } else if state.flushed < state.flush_requested {
+                let target = state.flush_requested;
+                drop(state);
+                let result = sender.flush();
+                state = self.lock();
+                state.record(result);
+                state.flushed = target;
+                self.changed.notify_all();
+            } else if state.closed {
+                return;
+            } else {
+                state = self.wait(state);
+            }
+        }
+    }
+}
+
+impl State {
+    fn record(&mut self, result: io::Result<()>) {
+        if let Err(err) = result {
+            self.error.get_or_insert(err);
+        }
+    }
+}
+
+/// Marks the worker as finished even if the wrapped sender panics.
+struct Finish<'a>(&'a Shared);
+
+impl Drop for Finish<'_> {
+    fn drop(&mut self) {
+        self.0.lock().finished = true;
+        self.0.changed.notify_all();
+    }
+}
+
+fn worker_gone() -> io::Error {
+    io::Error::new(io::ErrorKind::BrokenPipe, "the queue worker has exited")
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::mpsc;
+
+    use super::*;
+    use crate::sender::testing::Recorder;
+
+    #[test]
+    fn test_drop_oldest_and_drain() {
+        let gate = Arc::new(Mutex::new(()));
+        let (entered, entered_rx) = mpsc::channel();
+        let recorder = Recorder::gated(gate.clone(), entered);
+
+        let closed = gate.lock().unwrap();
+        let mut sender = AsyncQueueSender::new(recorder.clone(), 2).unwrap();
+        sender.set_policy(OverflowPolicy::DropOldest);
+        sender.send_formatted(b"1").unwrap();
+        // wait until the worker is blocked sending the first message
+        entered_rx.recv().unwrap();
+        for message in [b"2", b"3", b"4", b"5"] {
+            sender.send_formatted(message).unwrap();
+        }
+        assert_eq!(sender.dropped(), 2);
+        assert_eq!(sender.len(), 2);
+        drop(closed);
+
+        sender.flush().unwrap();
+        assert!(sender.is_empty());
+        assert_eq!(recorder.sent(), ["1", "4", "5"]);
+    }
+
+    #[test]
+    fn test_drop_newest() {
+        let gate = Arc::new(Mutex::new(()));
+        let (entered, entered_rx) = mpsc::channel();
+        let recorder = Recorder::gated(gate.clone(), entered);
+
+        let closed = gate.lock().unwrap();
+        let mut sender = AsyncQueueSender::new(recorder.clone(), 2).unwrap();
+        sender.set_policy(OverflowPolicy::DropNewest);
+        sender.send_formatted(b"1").unwrap();
+        entered_rx.recv().unwrap();
+        for message in [b"2", b"3", b"4", b"5"] {
+            sender.send_formatted(message).unwrap();
+        }
+        assert_eq!(sender.dropped(), 2);
+        assert_eq!(sender.len(), 2);
+        drop(closed);
+
+        sender.flush().unwrap();
+        assert!(sender.is_empty());
+        assert_eq!(recorder.sent(), ["1", "2", "3"]);
+    }
+
+    #[test]
+    fn test_drop_below() {
+        let gate = Arc::new(Mutex::new(()));
+        let (entered, entered_rx) = mpsc::channel();
+        let recorder = Recorder::gated(gate.clone(), entered);
+
+        let closed = gate.lock().unwrap();
+        let mut sender = AsyncQueueSender::new(recorder.clone(), 1).unwrap();
+        sender.set_policy(OverflowPolicy::DropBelow(Severity::WARNING));
+        sender.send_rfc3164(Severity::ERROR, "first").unwrap();
+        entered_rx.recv().unwrap();
+        sender.send_rfc3164(Severity::ERROR, "second").unwrap();
+        // less severe than the threshold: dropped instead of blocking
+        sender.send_rfc3164(Severity::NOTICE, "notice").unwrap();
+        sender.send_rfc3164(Severity::DEBUG, "debug").unwrap();
+        assert_eq!(sender.dropped(), 2);
+        assert_eq!(sender.len(), 1);
+
+        thread::scope(|scope| {
+            // as severe as the threshold: blocks until the worker makes room
+            let blocked = scope.spawn(|| sender.send_rfc3164(Severity::WARNING, "warning"));
+            thread::sleep(Duration::from_millis(50));
+            assert!(!blocked.is_finished());
+            drop(closed);
+            blocked.join().unwrap().unwrap();
+        });
+
+        sender.flush().unwrap();
+        assert_eq!(sender.dropped(), 2);
+        let sent = recorder.sent();
+        assert_eq!(sent.len(), 3);
+        for (sent, message) in sent.iter().zip(["first", "second", "warning"]) {
+            assert!(sent.ends_with(message), "{sent}");
+        }
+    }
+}
@@ -0,0 +1,295 @@
+// Copyright 2024 FastLabs Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::fmt;
+use std::io;
+use std::time::Duration;
+use std::time::Instant;
+
+use crate::format::RFC3164Format;
+use crate::format::SyslogContext;
+use crate::format::SyslogFormat;
+use crate::sender::Sender;
+use crate::Severity;
+
+/// A limit of `burst` messages per `interval`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RateLimit {
+    burst: u32,
+    interval: Duration,
+}
+
+impl RateLimit {
+    /// Create a new limit that allows bursts of up to `burst` messages, refilled evenly over
+    /// `interval`.
+    ///
+    /// A `burst` of 0 suppresses every message of the severities it applies to, and no summary is
+    /// ever sent for them, since the summary needs a token too. To drop severities on purpose, use
+    /// [`SyslogContext::mask`] instead.
+    pub fn new(burst: u32, interval: Duration) -> Self {
+        Self { burst, interval }
+    }
+}
+
+/// A sender that drops messages exceeding a rate limit.
+///
+/// Each severity has its own token bucket, which holds up to `burst` tokens and is refilled at
+/// `burst` tokens per `interval`. Sending a message takes a token, and messages sent while the
+/// bucket is empty are suppressed. Once a message of the severity can be sent again, a single
+/// `N messages suppressed` record of the same severity is sent before it, so that the loss is
+/// visible to the collector. This is similar to rsyslog's imuxsock rate limiting.
+///
+/// Messages sent with [`Sender::send_formatted`] have no known severity and are never limited.
+///
+/// # Example
+///
+/// ```rust, no_run
+/// use std::time::Duration;
+///
+/// use fasyslog::sender::RateLimit;
+/// use fasyslog::sender::RateLimitedSender;
+/// use fasyslog::sender::Sender;
+/// use fasyslog::Severity;
+///
+/// let udp = fasyslog::sender::udp_well_known().unwrap();
+/// let mut sender = RateLimitedSender::new(udp, RateLimit::new(200, Duration::from_secs(5)));
+/// // never limit the most severe messages
+/// sender.set_severity_limit(Severity::EMERGENCY, None);
+/// sender
+///     .send_rfc3164(Severity::ERROR, "Hello, syslog!")
+///     .unwrap();
+/// ```
+#[derive(Debug)]
+pub struct RateLimitedSender<S> {
+    sender: S,
+    context: SyslogContext,
+    buckets: [Option<Bucket>; 8],
+}
+
+#[derive(Debug)]
+struct Bucket {
+    limit: RateLimit,
+    tokens: f64,
+    updated: Instant,
+    suppressed: u64,
+}
+
+impl Bucket {
+    fn new(limit: RateLimit) -> Self {
+        Self {
+            limit,
+            tokens: f64::from(limit.burst),
+            updated: Instant::now(),
+            suppressed: 0,
+        }
+    }
+
+    fn refill(&mut self, now: Instant) {
+        let elapsed = now.saturating_duration_since(self.updated).as_secs_f64();
+        let interval = self.limit.interval.as_secs_f64();
+        let burst = f64::from(self.limit.burst);
+        self.tokens = if interval > 0.0 {
+            (self.tokens + elapsed * burst / interval).min(burst)
+        } else {
+            burst
+        };
+        self.updated = now;
+    }
+
+    fn try_take(&mut self) -> bool {
+        self.refill(Instant::now());
+        if self.tokens >= 1.0 {
+            self.tokens -= 1.0;
+            true
+        } else {
+            false
+        }
+    }
+}
+
+impl<S: Sender> RateLimitedSender<S> {
+    /// Create a new rate-limited sender that applies the given limit to every severity.
+    ///
+    /// The context of `sender` is copied to format messages with.
+    pub fn new(sender: S, limit: RateLimit) -> Self {
+        Self {
+            context: sender.context().clone(),
+            sender,
+            buckets: std::array::from_fn(|_| Some(Bucket::new(limit))),
+        }
+    }
+
+    /// Set the context when formatting Syslog message.
+    pub fn set_context(&mut self, context: SyslogContext) {
+        self.context = context;
+    }
+
+    /// Set the limit of the given severity, or remove it with `None`.
+    pub fn set_severity_limit(&mut self, severity: Severity, limit: Option<RateLimit>) {
+        self.buckets[usize::from(severity.code())] = limit.map(Bucket::new);
+    }
+
+    /// Returns the number of suppressed messages that have not been reported yet.
+    pub fn suppressed(&self) -> u64 {
+        self.buckets
+            .iter()
+            .flatten()
+            .map(|bucket| bucket.suppressed)
+            .sum()
+    }
+
+    /// Returns the wrapped sender.
+    pub fn get_ref(&self) -> &S {
+        &self.sender
+    }
+
+    /// Mutate the wrapped sender.
+    pub fn get_mut(&mut self) -> &mut S {
+        &mut self.sender
+    }
+
+    fn emit(
+        &mut self,
+        format: &dyn SyslogFormat,
+        severity: Severity,
+        message: &dyn fmt::Display,
+    ) -> io::Result<()> {
+        for formatted in self.context.format_message(format, severity, message)? {
+            self.sender.send_formatted(&formatted)?;
+        }
+        Ok(())
+    }
+
+    /// Send the suppression summary of the severity, if any.
+    fn report(&mut self, format: &dyn SyslogFormat, severity: Severity) -> io::Result<()> {
+        let Some(bucket) = &mut self.buckets[usize::from(severity.code())] else {
+            return Ok(());
+        };
+        let suppressed = std::mem::take(&mut bucket.suppressed);
+        if suppressed > 0 {
+            let message = format!("{suppressed} messages suppressed");
+            self.emit(format, severity, &message)?;
+        }
+        Ok(())
+    }
+}
+
+impl<S: Sender> Sender for RateLimitedSender<S> {
+    fn send_formatted(&mut self, formatted: &[u8]) -> io::Result<()> {
+        self.sender.send_formatted(formatted)
+    }
+
+    /// Send the suppression summaries of the severities whose limit has lifted, and flush the
+    /// wrapped sender.
+    fn flush(&mut self) -> io::Result<()> {
+        let format = self.context.default_format();
+        let format = format.as_deref().unwrap_or(&RFC3164Format);
+        for code in 0..8 {
+            let Some(bucket) = &mut self.buckets[usize::from(code)] else {
+                continue;
+            };
+            if bucket.suppressed > 0 {
+                bucket.refill(Instant::now());
+                if bucket.tokens >= 1.0 {
+                    let severity = Severity::try_from(code).expect("valid severity code");
+                    self.report(format, severity)?;
+                }
+            }
+        }
+        self.sender.flush()
+    }
+
+    fn context(&self) -> &SyslogContext {
+        &self.context
+    }
+
+    fn context_mut(&mut self) -> &mut SyslogContext {
+        &mut self.context
+    }
+
+    fn send_message(
+        &mut self,
+        format: &dyn SyslogFormat,
+        severity: Severity,
+        message: &dyn fmt::Display,
+    ) -> io::Result<()> {
+        if !self.context.enabled(severity) {
+            return Ok(());
+        }
+        if let Some(bucket) = &mut self.buckets[usize::from(severity.code())] {
+            if !bucket.try_take() {
+                bucket.suppressed += 1;
+                return Ok(());
+            }
+        }
+        self.report(format, severity)?;
+        self.emit(format, severity, message)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::format::LocalFormat;
+    use crate::sender::testing::Recorder;
+
+    #[test]
+    fn test_rate_limit_with_summary() {
+        let recorder = Recorder::default();
+        let limit = RateLimit::new(2, Duration::from_millis(100));
+        let mut sender = RateLimitedSender::new(recorder.clone(), limit);
+        sender.set_severity_limit(Severity::ALERT, None);
+        let mut context = SyslogContext::default();
+        context.format(LocalFormat);
+        sender.set_context(context);
+
+        for _ in 0..5 {
+            sender.send(Severity::ERROR, "flood").unwrap();
+        }
+        sender.send(Severity::ALERT, "alert").unwrap();
+        assert_eq!(sender.suppressed(), 3);
+        std::thread::sleep(Duration::from_millis(150));
+        sender.send(Severity::ERROR, "recovered").unwrap();
+        assert_eq!(sender.suppressed(), 0);
+
+        let sent = recorder.sent();
+        let messages = sent
+            .iter()
+            .map(|line| line.rsplit_once(": ").unwrap().1)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            messages,
+            [
+                "flood",
+                "flood",
+                "alert",
+                "3 messages suppressed",
+                "recovered"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_zero_burst() {
+        let recorder = Recorder::default();
+        let limit = RateLimit::new(0, Duration::ZERO);
+        let mut sender = RateLimitedSender::new(recorder.clone(), limit);
+        for _ in 0..3 {
+            sender.send_rfc3164(Severity::ERROR, "dropped").unwrap();
+        }
+        sender.flush().unwrap();
+        assert_eq!(sender.suppressed(), 3);
+        assert!(recorder.sent().is_empty());
+    }
+}
@@ -0,0 +1,389 @@
+// Copyright 2024 FastLabs Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::collections::hash_map::RandomState;
+use std::collections::VecDeque;
+use std::fmt;
+use std::hash::BuildHasher;
+use std::hash::Hasher;
+use std::io;
+use std::time::Duration;
+use std::time::Instant;
+
+use crate::format::SyslogContext;
+use crate::sender::Sender;
+
+/// An event reported by a [`ReconnectingSender`].
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum ReconnectEvent<'a> {
+    /// The connection was lost.
+    Disconnected {
+        /// The error that broke the connection.
+        error: &'a io::Error,
+    },
+    /// An attempt to reconnect failed.
+    ReconnectFailed {
+        /// The number of consecutive failed attempts, starting from 1.
+        attempt: u32,
+        /// The error of the attempt.
+        error: &'a io::Error,
+        /// The delay before the next attempt.
+        retry_in: Duration,
+    },
+    /// The connection was re-established.
+    Reconnected {
+        /// The number of attempts it took to reconnect.
+        attempts: u32,
+        /// The number of messages that were sent again over the new connection.
+        replayed: usize,
+    },
+}
+
+type Connect<S> = Box<dyn FnMut() -> io::Result<S> + Send>;
+type OnEvent = Box<dyn FnMut(&ReconnectEvent<'_>) + Send>;
+
+/// A sender that re-establishes its connection when it breaks.
+///
+/// Stream senders such as [`TcpSender`], [`NativeTlsSender`] and [`UnixStreamSender`] fail on
+/// every send once the peer has closed the connection, for example when the syslog server
+/// restarts. This wrapper creates a new sender with the given `connect` function when sending or
+/// flushing fails with a broken pipe, a reset, a write timeout, or a similar error.
+///
+/// Reconnection never sleeps on the caller's thread. After a failed attempt, sends fail fast with
+/// [`io::ErrorKind::NotConnected`] until the next attempt is due, which is delayed by an
+/// exponential backoff with jitter.
+///
+/// Since stream senders buffer their writes, a broken connection is often noticed only after some
+/// messages have been accepted. The wrapper keeps a copy of the messages sent since the last
+/// successful [`flush`](Sender::flush), up to the [replay limit], and sends them again once
+/// reconnected. Messages may thus be delivered twice, but are not silently lost.
+///
+/// [`TcpSender`]: crate::sender::TcpSender
+/// [`NativeTlsSender`]: crate::sender::NativeTlsSender
+/// [`UnixStreamSender`]: crate::sender::UnixStreamSender
+/// [replay limit]: ReconnectingSender::set_replay_limit
+///
+/// # Example
+///
+/// ```rust, no_run
+/// use fasyslog::sender::ReconnectingSender;
+/// use fasyslog::sender::Sender;
+///
+/// let mut sender = ReconnectingSender::new(|| fasyslog::sender::tcp("127.0.0.1:601")).unwrap();
+/// sender.on_event(|event| eprintln!("syslog connection: {event:?}"));
+/// sender
+///     .send_rfc3164(fasyslog::Severity::INFORMATIONAL, "Hello, syslog!")
+///     .unwrap();
+/// sender.flush().unwrap();
+/// ```
+pub struct ReconnectingSender<S> {
+    sender: Option<S>,
+    connect: Connect<S>,
+    context: SyslogContext,
+    on_event: Option<OnEvent>,
+    initial_backoff: Duration,
+    max_backoff: Duration,
+    attempts: u32,
+    next_attempt: Option<Instant>,
+    replay_limit: usize,
+    pending: VecDeque<Vec<u8>>,
+    pending_len: usize,
+}
+
+impl<S: fmt::Debug> fmt::Debug for ReconnectingSender<S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ReconnectingSender")
+            .field("sender", &self.sender)
+            .field("context", &self.context)
+            .field("attempts", &self.attempts)
+            .field("pending", &self.pending.len())
+            .finish_non_exhaustive()
+    }
+}
+
+impl<S: Sender> ReconnectingSender<S> {
+    /// Create a new reconnecting sender, and connect with the given function.
+    ///
+    /// The context starts as a copy of the context of the first connected sender.
+    pub fn new<F>(mut connect: F) -> io::Result<Self>
+    where
+        F: FnMut() -> io::Result<S> + Send + 'static,
+    {
+        let sender = connect()?;
+        Ok(Self {
+            context: sender.context().clone(),
+            sender: Some(sender),
+            connect: Box::new(connect),
+            on_event: None,
+            initial_backoff: Duration::from_millis(100),
+            max_backoff: Duration::from_secs(30),
+            attempts: 0,
+            next_attempt: None,
+            replay_limit: 64 * 1024,
+            pending: VecDeque::new(),
+            pending_len: 0,
+        })
+    }
+
+    /// Set the context when formatting Syslog message.
+    pub fn set_context(&mut self, context: SyslogContext) {
+        self.context = context;
+    }
+
+    /// Set the delay before the first reconnection attempt, and the maximum delay between
+    /// attempts.
+    ///
+    /// The delay doubles after every failed attempt, and is randomly reduced by up to a half to
+    /// avoid many clients reconnecting at once. Default is 100 milliseconds up to 30 seconds.
+    pub fn set_backoff(&mut self, initial: Duration, max: Duration) {
+        self.initial_backoff = initial;
+        self.max_backoff = max;
+    }
+
+    /// Set the maximum number of octets of unflushed messages to keep for sending again after
+    /// reconnecting.
+    ///
+    /// The oldest messages are discarded first, but the latest message is always kept. Default is
+    /// 64 KiB.
+    pub fn set_replay_limit(&mut self, replay_limit: usize) {
+        self.replay_limit = replay_limit;
+    }
+
+    /// Set the callback to report reconnection events to.
+    pub fn on_event<F>(&mut self, on_event: F)
+    where
+        F: FnMut(&ReconnectEvent<'_>) + Send + 'static,
+    {
+        self.on_event = Some(Box::new(on_event));
+    }
+
+    /// Returns whether the sender is currently connected.
+    pub fn is_connected(&self) -> bool {
+        self.sender.is_some()
+    }
+
+    fn report(&mut self, event: ReconnectEvent<'_>) {
+        if let Some(on_event) = &mut self.on_event {
+            on_event(&event);
+        }
+    }
+
+    fn remember(&mut self, formatted: &[u8]) {
+        self.pending.push_back(formatted.to_vec());
+        self.pending_len += formatted.len();
+        while self.pending_len > self.replay_limit && self.pending.len() > 1 {
+            if let Some(message) = self.pending.pop_front() {
+                self.pending_len -= message.len();
+            }
+        }
+    }
+
+    fn forget(&mut self) {
+        self.pending.clear();
+        self.pending_len = 0;
+    }
+
+    /// Handle an error of the wrapped sender, reconnecting and replaying the pending messages if
+    /// the connection is broken.
+    fn recover(&mut self, err: io::Error) -> io::Result<()> {
+        if self.sender.is_some() {
+            if !is_disconnect(&err) {
+                return Err(err);
+            }
+            self.sender = None;
+            self.report(ReconnectEvent::Disconnected { error: &err });
+        }
+
+        if let Some(next_attempt) = self.next_attempt {
+            let now = Instant::now();
+            if now < next_attempt {
+                return Err(io::Error::new(
+                    io::ErrorKind::NotConnected,
+                    format!("reconnecting in {:?}", next_attempt - now),
+                ));
+            }
+        }
+
+        self.attempts += 1;
+        let mut sender = match (self.connect)() {
+            Ok(sender) => sender,
+            Err(err) => {
+                let retry_in = self.schedule();
+                let attempt = self.attempts;
+                self.report(ReconnectEvent::ReconnectFailed {
+                    attempt,
+                    error: &err,
+                    retry_in,
+                });
+                return Err(err);
+            }
+        };
+
+        let replayed = self.pending.len();
+        for message in &self.pending {
+            if let Err(err) = sender.send_formatted(message) {
+                self.schedule();
+                self.report(ReconnectEvent::Disconnected { error: &err });
+                return Err(err);
+            }
+        }
+        let attempts = self.attempts;
+        self.sender = Some(sender);
+        self.attempts = 0;
+        self.next_attempt = None;
+        self.report(ReconnectEvent::Reconnected { attempts, replayed });
+        Ok(())
+    }
+
+    fn backoff(&self) -> Duration {
+        let exponent = self.attempts.saturating_sub(1).min(31);
+        let backoff = self
+            .initial_backoff
+            .saturating_mul(1 << exponent)
+            .min(self.max_backoff);
+        // Pick a random delay in [backoff / 2, backoff], without overflowing for huge backoffs.
+        let jitter = RandomState::new().build_hasher().finish() % 1024;
+        backoff / 2 + (backoff / 2 / 1024).saturating_mul(jitter as u32)
+    }
+
+    /// Schedule the next attempt after a random backoff, and return the delay.
+    fn schedule(&mut self) -> Duration {
+        // a delay that does not fit in an `Instant`, such as `Duration::MAX`, is ~136 years instead
+        const FAR_FUTURE: Duration = Duration::from_secs(u32::MAX as u64);
+        let retry_in = self.backoff();
+        let now = Instant::now();
+        self.next_attempt = now
+            .checked_add(retry_in)
+            .or_else(|| now.checked_add(FAR_FUTURE));
+        retry_in
+    }
+}
+
+impl<S: Sender> Sender for ReconnectingSender<S> {
+    fn send_formatted(&mut self, formatted: &[u8]) -> io::Result<()> {
+        self.remember(formatted);
+        let result = match &mut self.sender {
+            Some(sender) => sender.send_formatted(formatted),
+            None => Err(io::ErrorKind::NotConnected.into()),
+        };
+        // The pending messages, including this one, are sent on reconnection.
+        result.or_else(|err| self.recover(err))
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        let result = match &mut self.sender {
+            Some(sender) => sender.flush(),
+            None => Err(io::ErrorKind::NotConnected.into()),
+        };
+        if let Err(err) = result {
+            self.recover(err)?;
+            if let Some(sender) = &mut self.sender {
+                sender.flush()?;
+            }
+        }
+        self.forget();
+        Ok(())
+    }
+
+    fn context(&self) -> &SyslogContext {
+        &self.context
+    }
+
+    fn context_mut(&mut self) -> &mut SyslogContext {
+        &mut self.context
+    }
+}
+
+fn is_disconnect(err: &io::Error) -> bool {
+    matches!(
+        err.kind(),
+        io::ErrorKind::BrokenPipe
+            | io::ErrorKind::ConnectionReset
+            | io::ErrorKind::ConnectionAborted
+            | io::ErrorKind::NotConnected
+            | io::ErrorKind::UnexpectedEof
+            | io::ErrorKind::WriteZero
+            | io::ErrorKind::TimedOut
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::BufRead;
+    use std::io::BufReader;
+    use std::net::TcpListener;
+    use std::sync::atomic::AtomicBool;
+    use std::sync::atomic::Ordering;
+    use std::sync::Arc;
+
+    use super::*;
+    use crate::sender::testing::Recorder;
+
+    #[test]
+    fn test_backoff() {
+        let mut sender = ReconnectingSender::new(|| Ok(Recorder::default())).unwrap();
+        let ranges = [
+            (1, Duration::from_millis(50), Duration::from_millis(100)),
+            (4, Duration::from_millis(400), Duration::from_millis(800)),
+            (40, Duration::from_secs(15), Duration::from_secs(30)),
+        ];
+        for (attempts, min, max) in ranges {
+            sender.attempts = attempts;
+            let backoff = sender.backoff();
+            assert!(min <= backoff && backoff <= max, "{attempts}: {backoff:?}");
+        }
+
+        // huge delays must neither overflow nor expire
+        sender.set_backoff(Duration::MAX, Duration::MAX);
+        for attempts in [1, 40] {
+            sender.attempts = attempts;
+            assert!(sender.schedule() >= Duration::MAX / 2);
+            let next_attempt = sender.next_attempt.unwrap();
+            assert!(next_attempt > Instant::now() + Duration::from_secs(3600 * 24 * 365));
+        }
+    }
+
+    #[test]
+    fn test_reconnect_after_server_restart() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let mut sender = ReconnectingSender::new(move || crate::sender::tcp(addr)).unwrap();
+        sender.set_backoff(Duration::ZERO, Duration::ZERO);
+
+        let reconnected = Arc::new(AtomicBool::new(false));
+        sender.on_event({
+            let reconnected = reconnected.clone();
+            move |event| {
+                if let ReconnectEvent::Reconnected { .. } = event {
+                    reconnected.store(true, Ordering::SeqCst);
+                }
+            }
+        });
+
+        // Accept and immediately drop the first connection. Writes keep succeeding until the
+        // peer resets the connection, after which the sender reconnects transparently.
+        drop(listener.accept().unwrap());
+        while !reconnected.load(Ordering::SeqCst) {
+            sender.send_formatted(b"hello").unwrap();
+            sender.flush().unwrap();
+            std::thread::sleep(Duration::from_millis(10));
+        }
+
+        let (stream, _) = listener.accept().unwrap();
+        let mut line = String::new();
+        BufReader::new(stream).read_line(&mut line).unwrap();
+        assert_eq!(line, "hello\r\n");
+    }
+}
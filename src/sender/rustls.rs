@@ -0,0 +1,342 @@
+// Copyright 2024 FastLabs Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::borrow::Cow;
+use std::io;
+use std::io::BufWriter;
+use std::net::TcpStream;
+use std::net::ToSocketAddrs;
+use std::sync::Arc;
+use std::time::Duration;
+
+use rustls::client::WantsClientCert;
+use rustls::pki_types::ServerName;
+use rustls::ClientConfig;
+use rustls::ClientConnection;
+use rustls::ConfigBuilder;
+use rustls::RootCertStore;
+use rustls::StreamOwned;
+
+use crate::format::SyslogContext;
+use crate::sender::internal::impl_syslog_stream_sender;
+use crate::sender::Framing;
+use crate::sender::SocketOptions;
+
+/// Create a TLS sender that sends messages to the well-known port (6514).
+///
+/// See also [RFC-5425] §4.1 Port Assignment.
+///
+/// [RFC-5425]: https://datatracker.ietf.org/doc/html/rfc5425#section-4.1
+pub fn rustls_well_known<S: AsRef<str>>(domain: S) -> io::Result<RustlsSender> {
+    let domain = domain.as_ref();
+    rustls(format!("{domain}:6514"), domain)
+}
+
+/// Create a TLS sender that sends messages to the given address.
+///
+/// The server certificate is verified against the Mozilla root certificates bundled by
+/// [`webpki-roots`](https://crates.io/crates/webpki-roots).
+pub fn rustls<A: ToSocketAddrs, S: AsRef<str>>(addr: A, domain: S) -> io::Result<RustlsSender> {
+    let mut roots = RootCertStore::empty();
+    roots.extend(webpki_roots::TLS_SERVER_ROOTS.iter().cloned());
+    let config = rustls_config_builder(roots)?.with_no_client_auth();
+    rustls_with(addr, domain, Arc::new(config))
+}
+
+/// Create a TLS sender that sends messages to the given address with the given client config.
+///
+/// The config can trust custom root certificates, or authenticate the client with a certificate.
+/// See also [`rustls_config_builder`].
+pub fn rustls_with<A: ToSocketAddrs, S: AsRef<str>>(
+    addr: A,
+    domain: S,
+    config: Arc<ClientConfig>,
+) -> io::Result<RustlsSender> {
+    RustlsSender::connect(addr, domain, config)
+}
+
+/// Returns a builder of client configs that trust the given root certificates.
+///
+/// The configs use the [`ring`](rustls::crypto::ring) crypto provider, regardless of the process
+/// default provider. For example, to authenticate the client with a certificate:
+///
+/// ```rust, no_run
+/// use std::sync::Arc;
+///
+/// use rustls::pki_types::pem::PemObject;
+/// use rustls::pki_types::CertificateDer;
+/// use rustls::pki_types::PrivateKeyDer;
+///
+/// let mut roots = rustls::RootCertStore::empty();
+/// for cert in CertificateDer::pem_file_iter("ca.pem").unwrap() {
+///     roots.add(cert.unwrap()).unwrap();
+/// }
+/// let certs = CertificateDer::pem_file_iter("client.pem")
+///     .unwrap()
+///     .collect::<Result<Vec<_>, _>>()
+///     .unwrap();
+/// let key = PrivateKeyDer::from_pem_file("client.key").unwrap();
+/// let config = fasyslog::sender::rustls_config_builder(roots)
+///     .unwrap()
+///     .with_client_auth_cert(certs, key)
+///     .unwrap();
+/// let sender = fasyslog::sender::rustls_with(
+///     "logs.example.com:6514",
+///     "logs.example.com",
+///     Arc::new(config),
+/// )
+/// .unwrap();
+/// ```
+pub fn rustls_config_builder(
+    roots: RootCertStore,
+) -> io::Result<ConfigBuilder<ClientConfig, WantsClientCert>> {
+    let provider = Arc::new(rustls::crypto::ring::default_provider());
+    let builder = ClientConfig::builder_with_provider(provider)
+        .with_safe_default_protocol_versions()
+        .map_err(io::Error::other)?;
+    Ok(builder.with_root_certificates(roots))
+}
+
+/// A syslog sender that sends messages to a TCP socket over TLS, based on
+/// [`rustls`](https://crates.io/crates/rustls).
+///
+/// Users can obtain a `RustlsSender` by calling [`rustls_well_known`], [`rustls`], or
+/// [`rustls_with`].
+#[derive(Debug)]
+pub struct RustlsSender {
+    // the connection state is large; box it to keep `SyslogSender` small
+    writer: Box<BufWriter<StreamOwned<ClientConnection, TcpStream>>>,
+    context: SyslogContext,
+    postfix: Cow<'static, str>,
+    framing: Framing,
+    // set when a write timed out, since part of a message may have been written
+    poisoned: bool,
+}
+
+impl RustlsSender {
+    /// Connect to a TCP socket over TLS at the given address.
+    ///
+    /// The TLS handshake is completed before returning.
+    pub fn connect<A: ToSocketAddrs, S: AsRef<str>>(
+        addr: A,
+        domain: S,
+        config: Arc<ClientConfig>,
+    ) -> io::Result<Self> {
+        Self::handshake(TcpStream::connect(addr)?, domain.as_ref(), config, None)
+    }
+
+    /// Connect to a TCP socket over TLS at the given address, with timeouts.
+    ///
+    /// The TCP connection to every resolved address in turn is given `timeout`, and so is each
+    /// read or write of the TLS handshake. Both are reported as [`io::ErrorKind::TimedOut`]
+    /// errors when they expire.
+    pub fn connect_timeout<A: ToSocketAddrs, S: AsRef<str>>(
+        addr: A,
+        domain: S,
+        config: Arc<ClientConfig>,
+        timeout: Duration,
+    ) -> io::Result<Self> {
+        let stream = SocketOptions::new()
+            .connect_timeout(timeout)
+            .connect_tcp(addr)?;
+        Self::handshake(stream, domain.as_ref(), config, Some(timeout))
+    }
+
+    fn handshake(
+        mut stream: TcpStream,
+        domain: &str,
+        config: Arc<ClientConfig>,
+        timeout: Option<Duration>,
+    ) -> io::Result<Self> {
+        let server_name = ServerName::try_from(domain.to_string()).map_err(|err| {
+            io::Error::new(io::ErrorKind::InvalidInput, format!("{domain}: {err}"))
+        })?;
+        let mut conn = ClientConnection::new(config, server_name).map_err(io::Error::other)?;
+        stream.set_read_timeout(timeout)?;
+        stream.set_write_timeout(timeout)?;
+        while conn.is_handshaking() {
+            conn.complete_io(&mut stream)
+                .map_err(|err| match err.kind() {
+                    io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut => {
+                        let timeout = timeout.unwrap_or_default();
+                        let message =
+                            format!("TLS handshake with {domain} timed out after {timeout:?}");
+                        io::Error::new(io::ErrorKind::TimedOut, message)
+                    }
+                    _ => err,
+                })?;
+        }
+        stream.set_read_timeout(None)?;
+        stream.set_write_timeout(None)?;
+        Ok(Self {
+            writer: Box::new(BufWriter::new(StreamOwned::new(conn, stream))),
+            context: SyslogContext::default(),
+            postfix: Cow::Borrowed("\r\n"),
+            framing: Framing::NonTransparent,
+            poisoned: false,
+        })
+    }
+
+    /// Set the postfix when formatting Syslog message.
+    ///
+    /// This is generally '\r\n' as defined in [RFC-6587] §3.4.2.
+    ///
+    /// [RFC-6587]: https://datatracker.ietf.org/doc/html/rfc6587
+    pub fn set_postfix(&mut self, postfix: impl Into<Cow<'static, str>>) {
+        self.postfix = postfix.into();
+    }
+
+    /// Set the framing of messages sent over the stream.
+    ///
+    /// Default is [`Framing::NonTransparent`], which terminates messages with the postfix.
+    pub fn set_framing(&mut self, framing: Framing) {
+        self.framing = framing;
+    }
+
+    /// Set the write timeout of the underlying socket.
+    ///
+    /// Sending or flushing then returns an error of kind [`io::ErrorKind::TimedOut`] instead of
+    /// blocking forever on a stalled server. Default is `None`, which blocks indefinitely.
+    ///
+    /// Part of a message may have been sent when the timeout expires, so later sends and flushes
+    /// fail with [`io::ErrorKind::BrokenPipe`]. Wrap the sender in a
+    /// [`ReconnectingSender`](crate::sender::ReconnectingSender) to connect again.
+    pub fn set_write_timeout(&mut self, timeout: Option<Duration>) -> io::Result<()> {
+        self.writer.get_ref().sock.set_write_timeout(timeout)
+    }
+
+    /// Set the context when formatting Syslog message.
+    pub fn set_context(&mut self, context: SyslogContext) {
+        self.context = context;
+    }
+
+    /// Mutate the context when formatting Syslog message.
+    pub fn mut_context(&mut self) -> &mut SyslogContext {
+        &mut self.context
+    }
+}
+
+impl_syslog_stream_sender!(RustlsSender);
+
+#[cfg(test)]
+mod tests {
+    use std::io::Read;
+    use std::net::TcpListener;
+    use std::thread;
+
+    use rustls::pki_types::pem::PemObject;
+    use rustls::pki_types::CertificateDer;
+    use rustls::pki_types::PrivateKeyDer;
+    use rustls::ServerConfig;
+    use rustls::ServerConnection;
+
+    use super::*;
+    use crate::sender::testing::CERT;
+    use crate::sender::testing::KEY;
+    use crate::sender::Sender;
+    use crate::Severity;
+
+    fn client_config() -> Arc<ClientConfig> {
+        let mut roots = RootCertStore::empty();
+        roots
+            .add(CertificateDer::from_pem_slice(CERT).unwrap())
+            .unwrap();
+        Arc::new(rustls_config_builder(roots).unwrap().with_no_client_auth())
+    }
+
+    fn server_config() -> Arc<ServerConfig> {
+        let provider = Arc::new(rustls::crypto::ring::default_provider());
+        let config = ServerConfig::builder_with_provider(provider)
+            .with_safe_default_protocol_versions()
+            .unwrap()
+            .with_no_client_auth()
+            .with_single_cert(
+                vec![CertificateDer::from_pem_slice(CERT).unwrap()],
+                PrivateKeyDer::from_pem_slice(KEY).unwrap(),
+            )
+            .unwrap();
+        Arc::new(config)
+    }
+
+    #[test]
+    fn test_send_over_tls() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let server = thread::spawn(move || {
+            let (stream, _) = listener.accept().unwrap();
+            let conn = ServerConnection::new(server_config()).unwrap();
+            let mut stream = StreamOwned::new(conn, stream);
+            let mut received = Vec::new();
+            let mut buf = [0; 1024];
+            // the client closes the connection without a TLS close_notify
+            while let Ok(len @ 1..) = stream.read(&mut buf) {
+                received.extend_from_slice(&buf[..len]);
+            }
+            String::from_utf8(received).unwrap()
+        });
+
+        let mut sender = RustlsSender::connect_timeout(
+            addr,
+            "localhost",
+            client_config(),
+            Duration::from_secs(5),
+        )
+        .unwrap();
+        sender.set_framing(Framing::OctetCounting);
+        sender.send_rfc3164(Severity::NOTICE, "one").unwrap();
+        sender.send_rfc3164(Severity::NOTICE, "two").unwrap();
+        sender.flush().unwrap();
+        drop(sender);
+
+        let received = server.join().unwrap();
+        let mut rest = received.as_str();
+        for expected in ["one", "two"] {
+            let (len, tail) = rest.split_once(' ').unwrap();
+            let (message, tail) = tail.split_at(len.parse().unwrap());
+            assert!(message.starts_with("<13>"), "{received:?}");
+            assert!(message.ends_with(&format!(": {expected}")), "{received:?}");
+            rest = tail;
+        }
+        assert!(rest.is_empty(), "{received:?}");
+    }
+
+    #[test]
+    fn test_handshake_timeout() {
+        // the connection is queued by the kernel, but the server never answers the handshake
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let timeout = Duration::from_millis(100);
+        let err =
+            RustlsSender::connect_timeout(addr, "localhost", client_config(), timeout).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::TimedOut, "{err}");
+        drop(listener);
+    }
+
+    #[test]
+    fn test_untrusted_certificate() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let server = thread::spawn(move || {
+            let (stream, _) = listener.accept().unwrap();
+            let conn = ServerConnection::new(server_config()).unwrap();
+            let mut stream = StreamOwned::new(conn, stream);
+            let _ = stream.read(&mut [0; 1]);
+        });
+
+        // the self-signed certificate is not among the bundled roots
+        let err = rustls(addr, "localhost").unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData, "{err}");
+        server.join().unwrap();
+    }
+}
@@ -0,0 +1,289 @@
+// Copyright 2024 FastLabs Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::fmt;
+use std::io;
+use std::net::UdpSocket;
+#[cfg(unix)]
+use std::os::unix::net::UnixDatagram;
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::sync::MutexGuard;
+use std::sync::PoisonError;
+use std::sync::RwLock;
+
+use crate::format::RFC3164Format;
+use crate::format::RFC5424Format;
+use crate::format::SyslogContext;
+use crate::format::SyslogFormat;
+use crate::sender::Sender;
+use crate::sender::SyslogSender;
+use crate::SDElement;
+use crate::Severity;
+
+/// A thread-safe handle to a sender, cheap to clone and share across threads.
+///
+/// All methods take `&self`. Messages are formatted by the calling thread without holding any
+/// lock, and only writing the formatted message is serialized. UDP and Unix datagram senders are
+/// not locked at all, since sending a datagram is atomic. Journald and custom senders may
+/// override how messages are formatted, so they format messages while holding the lock.
+///
+/// The context of the wrapped sender becomes the context of the handle, and can be replaced at
+/// runtime with [`set_context`](SharedSender::set_context) or
+/// [`update_context`](SharedSender::update_context).
+///
+/// # Example
+///
+/// ```rust, no_run
+/// use fasyslog::sender::SharedSender;
+/// use fasyslog::sender::SyslogSender;
+///
+/// let udp = fasyslog::sender::udp_well_known().unwrap();
+/// let sender = SharedSender::new(SyslogSender::Udp(udp));
+/// let handles = (0..4)
+///     .map(|i| {
+///         let sender = sender.clone();
+///         std::thread::spawn(move || {
+///             sender
+///                 .send_rfc3164(fasyslog::Severity::INFORMATIONAL, format!("worker {i}"))
+///                 .unwrap();
+///         })
+///     })
+///     .collect::<Vec<_>>();
+/// for handle in handles {
+///     handle.join().unwrap();
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct SharedSender {
+    inner: Arc<Inner>,
+}
+
+#[derive(Debug)]
+struct Inner {
+    context: RwLock<Arc<SyslogContext>>,
+    transport: Transport,
+}
+
+#[derive(Debug)]
+enum Transport {
+    Udp(UdpSocket),
+    #[cfg(unix)]
+    UnixDatagram(UnixDatagram),
+    /// Senders that only need their writes serialized.
+    Locked(Mutex<SyslogSender>),
+    /// Senders that format messages themselves.
+    Delegated(Mutex<SyslogSender>),
+}
+
+impl SharedSender {
+    /// Create a new shared handle to the given sender.
+    pub fn new(sender: SyslogSender) -> Self {
+        let (context, transport) = match sender {
+            SyslogSender::Udp(sender) => {
+                let (socket, context) = sender.into_parts();
+                (context, Transport::Udp(socket))
+            }
+            #[cfg(unix)]
+            SyslogSender::UnixDatagram(sender) => {
+                let (socket, context) = sender.into_parts();
+                (context, Transport::UnixDatagram(socket))
+            }
+            #[cfg(target_os = "linux")]
+            sender @ SyslogSender::Journald(_) => {
+                let context = sender.context().clone();
+                (context, Transport::Delegated(Mutex::new(sender)))
+            }
+            sender @ SyslogSender::Custom(_) => {
+                let context = sender.context().clone();
+                (context, Transport::Delegated(Mutex::new(sender)))
+            }
+            sender => {
+                let context = sender.context().clone();
+                (context, Transport::Locked(Mutex::new(sender)))
+            }
+        };
+        Self {
+            inner: Arc::new(Inner {
+                context: RwLock::new(Arc::new(context)),
+                transport,
+            }),
+        }
+    }
+
+    /// Returns a snapshot of the context when formatting Syslog message.
+    pub fn context(&self) -> Arc<SyslogContext> {
+        let context = self.inner.context.read();
+        context.unwrap_or_else(PoisonError::into_inner).clone()
+    }
+
+    /// Set the context when formatting Syslog message, for all clones of this handle.
+    pub fn set_context(&self, context: SyslogContext) {
+        if let Transport::Delegated(sender) = &self.inner.transport {
+            *lock(sender).context_mut() = context.clone();
+        }
+        let current = self.inner.context.write();
+        *current.unwrap_or_else(PoisonError::into_inner) = Arc::new(context);
+    }
+
+    /// Mutate a copy of the context and set it, for all clones of this handle.
+    ///
+    /// For example, this can change the severity mask at runtime:
+    ///
+    /// ```rust, no_run
+    /// # let sender = fasyslog::sender::SharedSender::new(
+    /// #     fasyslog::sender::SyslogSender::Udp(fasyslog::sender::udp_well_known().unwrap()),
+    /// # );
+    /// use fasyslog::Severity;
+    /// use fasyslog::SeverityMask;
+    ///
+    /// sender.update_context(|context| {
+    ///     context.mask(SeverityMask::up_to(Severity::NOTICE));
+    /// });
+    /// ```
+    pub fn update_context(&self, update: impl FnOnce(&mut SyslogContext)) {
+        let mut context = SyslogContext::clone(&self.context());
+        update(&mut context);
+        self.set_context(context);
+    }
+
+    /// Send a pre-formatted message.
+    pub fn send_formatted(&self, formatted: &[u8]) -> io::Result<()> {
+        match &self.inner.transport {
+            Transport::Udp(socket) => socket.send(formatted).map(|_| ()),
+            #[cfg(unix)]
+            Transport::UnixDatagram(socket) => socket.send(formatted).map(|_| ()),
+            Transport::Locked(sender) | Transport::Delegated(sender) => {
+                lock(sender).send_formatted(formatted)
+            }
+        }
+    }
+
+    /// Flush the wrapped sender.
+    ///
+    /// See also [`Sender::flush`].
+    pub fn flush(&self) -> io::Result<()> {
+        match &self.inner.transport {
+            Transport::Udp(_) => Ok(()),
+            #[cfg(unix)]
+            Transport::UnixDatagram(_) => Ok(()),
+            Transport::Locked(sender) | Transport::Delegated(sender) => lock(sender).flush(),
+        }
+    }
+
+    /// Send a message with the given severity in the given format.
+    ///
+    /// See also [`Sender::send_message`].
+    pub fn send_message(
+        &self,
+        format: &dyn SyslogFormat,
+        severity: Severity,
+        message: &dyn fmt::Display,
+    ) -> io::Result<()> {
+        let context = self.context();
+        if !context.enabled(severity) {
+            return Ok(());
+        }
+        if let Transport::Delegated(sender) = &self.inner.transport {
+            return lock(sender).send_message(format, severity, message);
+        }
+        for formatted in context.format_message(format, severity, message)? {
+            self.send_formatted(&formatted)?;
+        }
+        Ok(())
+    }
+
+    /// Send a message with the given severity in the given format.
+    pub fn send_with<M: fmt::Display>(
+        &self,
+        format: &dyn SyslogFormat,
+        severity: Severity,
+        message: M,
+    ) -> io::Result<()> {
+        self.send_message(format, severity, &message)
+    }
+
+    /// Send a message with the given severity in the default format of the context.
+    ///
+    /// See also [`SyslogContext::format`].
+    pub fn send<M: fmt::Display>(&self, severity: Severity, message: M) -> io::Result<()> {
+        match self.context().default_format() {
+            Some(format) => self.send_message(&*format, severity, &message),
+            None => self.send_message(&RFC3164Format, severity, &message),
+        }
+    }
+
+    /// Send a message with the given severity as defined in RFC-3164.
+    pub fn send_rfc3164<M: fmt::Display>(&self, severity: Severity, message: M) -> io::Result<()> {
+        self.send_message(&RFC3164Format, severity, &message)
+    }
+
+    /// Send a message with the given severity as defined in RFC-5424.
+    pub fn send_rfc5424<S: Into<String>, M: fmt::Display>(
+        &self,
+        severity: Severity,
+        msgid: Option<S>,
+        elements: Vec<SDElement>,
+        message: M,
+    ) -> io::Result<()> {
+        let format = RFC5424Format::new(msgid, elements);
+        self.send_message(&format, severity, &message)
+    }
+}
+
+fn lock(sender: &Mutex<SyslogSender>) -> MutexGuard<'_, SyslogSender> {
+    sender.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::UdpSocket;
+
+    use super::*;
+    use crate::SeverityMask;
+
+    #[test]
+    fn test_shared_udp_sender() {
+        let server = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let udp = crate::sender::udp("127.0.0.1:0", server.local_addr().unwrap()).unwrap();
+        let sender = SharedSender::new(SyslogSender::Udp(udp));
+        sender.update_context(|context| {
+            context.mask(SeverityMask::up_to(Severity::NOTICE));
+        });
+
+        let handles = (0..4)
+            .map(|i| {
+                let sender = sender.clone();
+                std::thread::spawn(move || {
+                    sender.send_rfc3164(Severity::DEBUG, "dropped").unwrap();
+                    sender.send_rfc3164(Severity::NOTICE, i).unwrap();
+                })
+            })
+            .collect::<Vec<_>>();
+        for handle in handles {
+            handle.join().unwrap();
+        }
+
+        let mut received = Vec::new();
+        let mut buf = [0; 1024];
+        for _ in 0..4 {
+            let len = server.recv(&mut buf).unwrap();
+            let message = String::from_utf8_lossy(&buf[..len]).into_owned();
+            assert!(message.starts_with("<13>"), "{message}");
+            received.push(message.chars().last().unwrap());
+        }
+        received.sort();
+        assert_eq!(received, ['0', '1', '2', '3']);
+    }
+}
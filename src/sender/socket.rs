@@ -0,0 +1,262 @@
+// Copyright 2024 FastLabs Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::io;
+use std::net::SocketAddr;
+use std::net::TcpStream;
+use std::net::ToSocketAddrs;
+use std::net::UdpSocket;
+use std::time::Duration;
+
+use socket2::Domain;
+use socket2::Protocol;
+use socket2::SockAddr;
+use socket2::Socket;
+use socket2::TcpKeepalive;
+use socket2::Type;
+
+/// Socket-level settings of network senders.
+///
+/// Settings that are not set are left to the defaults of the operating system. Settings that do
+/// not apply to a transport, such as [`nodelay`](SocketOptions::nodelay) for UDP, are ignored.
+///
+/// # Example
+///
+/// ```rust, no_run
+/// use std::time::Duration;
+///
+/// use fasyslog::sender::SocketOptions;
+/// use fasyslog::sender::TcpSender;
+///
+/// let options = SocketOptions::new()
+///     .connect_timeout(Duration::from_secs(5))
+///     .nodelay(true)
+///     .keepalive(Duration::from_secs(60))
+///     // CS2, the OAM class recommended by RFC 4594
+///     .dscp(16);
+/// let sender = TcpSender::connect_with("logs.example.com:601", &options).unwrap();
+/// ```
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct SocketOptions {
+    local_addr: Option<SocketAddr>,
+    connect_timeout: Option<Duration>,
+    nodelay: Option<bool>,
+    keepalive: Option<Duration>,
+    send_buffer_size: Option<usize>,
+    dscp: Option<u8>,
+    #[cfg(target_os = "linux")]
+    device: Option<String>,
+}
+
+impl SocketOptions {
+    /// Create new options that leave every setting to the operating system.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Bind the socket to the given local address and port before connecting.
+    ///
+    /// Remote addresses of another address family than `addr` are skipped.
+    pub fn bind(mut self, addr: SocketAddr) -> Self {
+        self.local_addr = Some(addr);
+        self
+    }
+
+    /// Set the maximum time to wait for the connection to every resolved address in turn.
+    ///
+    /// An expired timeout is reported as an [`io::ErrorKind::TimedOut`] error. This only applies
+    /// to stream transports.
+    pub fn connect_timeout(mut self, timeout: Duration) -> Self {
+        self.connect_timeout = Some(timeout);
+        self
+    }
+
+    /// Set the `TCP_NODELAY` option, which disables Nagle's algorithm.
+    pub fn nodelay(mut self, nodelay: bool) -> Self {
+        self.nodelay = Some(nodelay);
+        self
+    }
+
+    /// Enable TCP keepalive, probing an idle connection after `interval` and then every
+    /// `interval` until it is considered broken.
+    ///
+    /// Some platforms only support whole seconds, or do not support setting the interval between
+    /// probes, in which case only the idle time is set.
+    pub fn keepalive(mut self, interval: Duration) -> Self {
+        self.keepalive = Some(interval);
+        self
+    }
+
+    /// Set the `SO_SNDBUF` option, the size of the send buffer of the socket in bytes.
+    pub fn send_buffer_size(mut self, size: usize) -> Self {
+        self.send_buffer_size = Some(size);
+        self
+    }
+
+    /// Mark outgoing packets with the given Differentiated Services Code Point, as defined in
+    /// [RFC-2474].
+    ///
+    /// The DSCP is the upper 6 bits of the `IP_TOS` option for IPv4, and of the `IPV6_TCLASS`
+    /// option for IPv6. Values above 63 are rejected when connecting.
+    ///
+    /// [RFC-2474]: https://datatracker.ietf.org/doc/html/rfc2474#section-3
+    pub fn dscp(mut self, dscp: u8) -> Self {
+        self.dscp = Some(dscp);
+        self
+    }
+
+    /// (linux only) Bind the socket to the given network interface with `SO_BINDTODEVICE`, such
+    /// as `eth1`.
+    ///
+    /// This usually requires the `CAP_NET_RAW` capability.
+    #[cfg(target_os = "linux")]
+    pub fn bind_device(mut self, device: impl Into<String>) -> Self {
+        self.device = Some(device.into());
+        self
+    }
+
+    /// Connect a TCP stream to the first reachable address.
+    pub(crate) fn connect_tcp<A: ToSocketAddrs>(&self, addr: A) -> io::Result<TcpStream> {
+        self.try_each(addr, |remote| {
+            let socket = self.socket(remote, Type::STREAM, Protocol::TCP)?;
+            if let Some(nodelay) = self.nodelay {
+                socket.set_tcp_nodelay(nodelay)?;
+            }
+            if let Some(interval) = self.keepalive {
+                let keepalive = TcpKeepalive::new().with_time(interval);
+                #[cfg(any(target_os = "linux", target_os = "macos", target_os = "windows"))]
+                let keepalive = keepalive.with_interval(interval);
+                socket.set_tcp_keepalive(&keepalive)?;
+            }
+            let remote = SockAddr::from(*remote);
+            match self.connect_timeout {
+                Some(timeout) => socket.connect_timeout(&remote, timeout).map_err(|err| {
+                    if err.kind() == io::ErrorKind::TimedOut {
+                        let message = format!("connect timed out after {timeout:?}");
+                        io::Error::new(io::ErrorKind::TimedOut, message)
+                    } else {
+                        err
+                    }
+                })?,
+                None => socket.connect(&remote)?,
+            }
+            Ok(socket.into())
+        })
+    }
+
+    /// Create a UDP socket connected to the first address that can be connected to.
+    pub(crate) fn connect_udp<A: ToSocketAddrs>(&self, addr: A) -> io::Result<UdpSocket> {
+        self.try_each(addr, |remote| {
+            let socket = self.socket(remote, Type::DGRAM, Protocol::UDP)?;
+            socket.connect(&SockAddr::from(*remote))?;
+            Ok(socket.into())
+        })
+    }
+
+    fn try_each<A: ToSocketAddrs, T>(
+        &self,
+        addr: A,
+        mut connect: impl FnMut(&SocketAddr) -> io::Result<T>,
+    ) -> io::Result<T> {
+        let mut last_err = None;
+        for remote in addr.to_socket_addrs()? {
+            if let Some(local) = self.local_addr {
+                if local.is_ipv4() != remote.is_ipv4() {
+                    continue;
+                }
+            }
+            match connect(&remote) {
+                Ok(socket) => return Ok(socket),
+                Err(err) => last_err = Some(err),
+            }
+        }
+        Err(last_err.unwrap_or_else(|| {
+            let message = match self.local_addr {
+                Some(local) => format!("no address to connect to from {local}"),
+                None => "could not resolve to any addresses".to_string(),
+            };
+            io::Error::new(io::ErrorKind::InvalidInput, message)
+        }))
+    }
+
+    fn socket(&self, remote: &SocketAddr, ty: Type, protocol: Protocol) -> io::Result<Socket> {
+        let socket = Socket::new(Domain::for_address(*remote), ty, Some(protocol))?;
+        if let Some(size) = self.send_buffer_size {
+            socket.set_send_buffer_size(size)?;
+        }
+        if let Some(dscp) = self.dscp {
+            if dscp > 63 {
+                let message = format!("DSCP must be at most 63: {dscp}");
+                return Err(io::Error::new(io::ErrorKind::InvalidInput, message));
+            }
+            set_dscp(&socket, remote, dscp)?;
+        }
+        #[cfg(target_os = "linux")]
+        if let Some(device) = &self.device {
+            socket.bind_device(Some(device.as_bytes()))?;
+        }
+        if let Some(local) = self.local_addr {
+            socket.bind(&SockAddr::from(local))?;
+        }
+        Ok(socket)
+    }
+}
+
+fn set_dscp(socket: &Socket, remote: &SocketAddr, dscp: u8) -> io::Result<()> {
+    let tos = u32::from(dscp) << 2;
+    if remote.is_ipv4() {
+        return socket.set_tos_v4(tos);
+    }
+    #[cfg(any(target_os = "linux", target_os = "macos"))]
+    return socket.set_tclass_v6(tos);
+    #[cfg(not(any(target_os = "linux", target_os = "macos")))]
+    Err(io::Error::new(
+        io::ErrorKind::Unsupported,
+        "DSCP marking of IPv6 traffic is not supported on this platform",
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::TcpListener;
+
+    use super::*;
+
+    #[test]
+    fn test_socket_options() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        // let the system pick the local port, since a port probed beforehand may be taken again
+        let local: SocketAddr = "127.0.0.1:0".parse().unwrap();
+        let options = SocketOptions::new()
+            .bind(local)
+            .nodelay(true)
+            .keepalive(Duration::from_secs(30))
+            .send_buffer_size(64 * 1024)
+            .dscp(16);
+
+        let stream = options.connect_tcp(listener.local_addr().unwrap()).unwrap();
+        let bound = stream.local_addr().unwrap();
+        assert_eq!(bound.ip(), local.ip());
+        assert_ne!(bound.port(), 0);
+        assert!(stream.nodelay().unwrap());
+        let socket = Socket::from(stream);
+        assert!(socket.keepalive().unwrap());
+        assert_eq!(socket.tos_v4().unwrap(), 16 << 2);
+
+        let err = options.clone().dscp(64).connect_udp("127.0.0.1:514");
+        assert_eq!(err.unwrap_err().kind(), io::ErrorKind::InvalidInput);
+        let err = options.connect_udp("[::1]:514").unwrap_err();
+        assert!(err.to_string().contains("no address"), "{err}");
+    }
+}
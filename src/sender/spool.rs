@@ -0,0 +1,353 @@
+// Copyright 2024 FastLabs Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::collections::VecDeque;
+use std::fs;
+use std::fs::File;
+use std::fs::OpenOptions;
+use std::io;
+use std::io::Write;
+use std::path::PathBuf;
+use std::time::Duration;
+use std::time::Instant;
+
+use crate::format::SyslogContext;
+use crate::sender::Sender;
+use crate::sender::SyslogSender;
+
+const SEGMENT_EXTENSION: &str = "spool";
+
+/// When a [`SpoolSender`] syncs spooled messages to disk.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum FsyncPolicy {
+    /// Sync after every spooled message.
+    Always,
+    /// Sync when the sender is flushed.
+    #[default]
+    OnFlush,
+    /// Leave syncing to the operating system.
+    Never,
+}
+
+/// A sender that spools messages to disk while the wrapped sender fails.
+///
+/// When sending a message fails, the formatted message is appended to a queue of segment files in
+/// the spool directory instead, and the error is not returned. Once the wrapped sender recovers,
+/// the spooled messages are replayed in order before any new message. Replaying is attempted on
+/// every message sent after the retry interval has elapsed, and on every flush. Messages left in
+/// the spool directory are picked up by the next `SpoolSender` created on it, so they survive
+/// process restarts.
+///
+/// Messages are delivered at least once: a message may be sent again if the process exits while
+/// replaying. Errors are returned only if a message can be neither sent nor spooled, for example
+/// when the spool has reached its maximum size. This is similar to rsyslog's disk-assisted
+/// queues.
+///
+/// Stream senders buffer writes and cannot recover from a broken connection on their own, so they
+/// should be wrapped in a [`ReconnectingSender`](crate::sender::ReconnectingSender) for their
+/// failures to be spooled.
+///
+/// # Example
+///
+/// ```rust, no_run
+/// use fasyslog::sender::Sender;
+/// use fasyslog::sender::SpoolSender;
+/// use fasyslog::sender::SyslogSender;
+///
+/// let udp = fasyslog::sender::udp_well_known().unwrap();
+/// let mut sender = SpoolSender::new(SyslogSender::Udp(udp), "/var/spool/myapp").unwrap();
+/// sender
+///     .send_rfc3164(fasyslog::Severity::NOTICE, "user logged in")
+///     .unwrap();
+/// sender.flush().unwrap();
+/// ```
+#[derive(Debug)]
+pub struct SpoolSender {
+    sender: SyslogSender,
+    context: SyslogContext,
+    dir: PathBuf,
+    segment_size: u64,
+    max_size: u64,
+    fsync: FsyncPolicy,
+    retry_interval: Duration,
+    next_retry: Option<Instant>,
+    segments: VecDeque<Segment>,
+    writer: Option<File>,
+    head: Option<Head>,
+    size: u64,
+}
+
+#[derive(Debug)]
+struct Segment {
+    seq: u64,
+    size: u64,
+}
+
+/// The segment being replayed.
+#[derive(Debug)]
+struct Head {
+    seq: u64,
+    data: Vec<u8>,
+    offset: usize,
+}
+
+impl SpoolSender {
+    /// Create a new spooling sender that spools messages in the given directory.
+    ///
+    /// The directory is created if it does not exist. Messages already spooled in it are replayed
+    /// before any new message. The context is copied from `sender`.
+    pub fn new(sender: SyslogSender, dir: impl Into<PathBuf>) -> io::Result<Self> {
+        let dir = dir.into();
+        fs::create_dir_all(&dir)?;
+        let mut segments = Vec::new();
+        for entry in fs::read_dir(&dir)? {
+            let path = entry?.path();
+            if path.extension().is_some_and(|ext| ext == SEGMENT_EXTENSION) {
+                let seq = path
+                    .file_stem()
+                    .and_then(|stem| stem.to_str()?.parse().ok());
+                if let Some(seq) = seq {
+                    let size = fs::metadata(&path)?.len();
+                    segments.push(Segment { seq, size });
+                }
+            }
+        }
+        segments.sort_by_key(|segment| segment.seq);
+        let size = segments.iter().map(|segment| segment.size).sum();
+        Ok(Self {
+            context: sender.context().clone(),
+            sender,
+            dir,
+            segment_size: 1024 * 1024,
+            max_size: 64 * 1024 * 1024,
+            fsync: FsyncPolicy::default(),
+            retry_interval: Duration::from_secs(1),
+            next_retry: None,
+            segments: segments.into(),
+            writer: None,
+            head: None,
+            size,
+        })
+    }
+
+    /// Set the context when formatting Syslog message.
+    pub fn set_context(&mut self, context: SyslogContext) {
+        self.context = context;
+    }
+
+    /// Set the size in bytes after which a new segment file is started.
+    ///
+    /// Default is 1 MiB.
+    pub fn set_segment_size(&mut self, segment_size: u64) {
+        self.segment_size = segment_size;
+    }
+
+    /// Set the maximum size in bytes of all segment files.
+    ///
+    /// Default is 64 MiB.
+    pub fn set_max_size(&mut self, max_size: u64) {
+        self.max_size = max_size;
+    }
+
+    /// Set when spooled messages are synced to disk.
+    ///
+    /// Default is [`FsyncPolicy::OnFlush`].
+    pub fn set_fsync(&mut self, fsync: FsyncPolicy) {
+        self.fsync = fsync;
+    }
+
+    /// Set the minimum interval between attempts to replay spooled messages when sending.
+    ///
+    /// Default is 1 second.
+    pub fn set_retry_interval(&mut self, retry_interval: Duration) {
+        self.retry_interval = retry_interval;
+    }
+
+    /// Returns the size in bytes of the spooled messages.
+    pub fn spooled_size(&self) -> u64 {
+        self.size
+    }
+
+    /// Returns `true` if there is no spooled message.
+    pub fn is_empty(&self) -> bool {
+        self.segments.is_empty()
+    }
+
+    fn segment_path(&self, seq: u64) -> PathBuf {
+        self.dir.join(format!("{seq:020}.{SEGMENT_EXTENSION}"))
+    }
+
+    fn retry_due(&self) -> bool {
+        match self.next_retry {
+            Some(next_retry) => Instant::now() >= next_retry,
+            None => true,
+        }
+    }
+
+    /// Send the spooled messages in order, removing each segment once it is fully sent.
+    fn replay(&mut self) -> io::Result<()> {
+        while let Some(seq) = self.segments.front().map(|segment| segment.seq) {
+            if self.head.as_ref().map(|head| head.seq) != Some(seq) {
+                if self.segments.len() == 1 {
+                    // new messages go to a new segment while this one is replayed
+                    self.writer = None;
+                }
+                let data = fs::read(self.segment_path(seq))?;
+                self.head = Some(Head {
+                    seq,
+                    data,
+                    offset: 0,
+                });
+            }
+            let head = self.head.as_mut().expect("head segment is loaded");
+            while let Some((record, next)) = next_record(&head.data, head.offset) {
+                if let Err(err) = self.sender.send_formatted(record) {
+                    self.next_retry = Some(Instant::now() + self.retry_interval);
+                    return Err(err);
+                }
+                head.offset = next;
+            }
+            fs::remove_file(self.segment_path(seq))?;
+            if let Some(segment) = self.segments.pop_front() {
+                self.size -= segment.size;
+            }
+            self.head = None;
+        }
+        self.next_retry = None;
+        Ok(())
+    }
+
+    /// Append the formatted message to the last segment.
+    fn spool(&mut self, formatted: &[u8]) -> io::Result<()> {
+        let len = u32::try_from(formatted.len())
+            .map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, "message is too long"))?;
+        let record_size = 4 + u64::from(len);
+        if self.size + record_size > self.max_size {
+            let message = format!("spool {} is full", self.dir.display());
+            return Err(io::Error::other(message));
+        }
+
+        let rotate = match self.segments.back() {
+            Some(segment) => {
+                self.writer.is_none() || segment.size + record_size > self.segment_size
+            }
+            None => true,
+        };
+        if rotate {
+            let seq = self.segments.back().map_or(0, |segment| segment.seq + 1);
+            let file = OpenOptions::new()
+                .create_new(true)
+                .append(true)
+                .open(self.segment_path(seq))?;
+            self.writer = Some(file);
+            self.segments.push_back(Segment { seq, size: 0 });
+        }
+
+        let writer = self.writer.as_mut().expect("segment is open");
+        let mut record = Vec::with_capacity(record_size as usize);
+        record.extend_from_slice(&len.to_be_bytes());
+        record.extend_from_slice(formatted);
+        writer.write_all(&record)?;
+        if self.fsync == FsyncPolicy::Always {
+            writer.sync_data()?;
+        }
+        let segment = self.segments.back_mut().expect("segment is open");
+        segment.size += record_size;
+        self.size += record_size;
+        Ok(())
+    }
+}
+
+impl Sender for SpoolSender {
+    fn send_formatted(&mut self, formatted: &[u8]) -> io::Result<()> {
+        if !self.segments.is_empty() && (!self.retry_due() || self.replay().is_err()) {
+            return self.spool(formatted);
+        }
+        if self.sender.send_formatted(formatted).is_err() {
+            self.next_retry = Some(Instant::now() + self.retry_interval);
+            return self.spool(formatted);
+        }
+        Ok(())
+    }
+
+    /// Replay the spooled messages and flush the wrapped sender.
+    ///
+    /// Spooled messages that still cannot be sent stay in the spool and are not reported as an
+    /// error.
+    fn flush(&mut self) -> io::Result<()> {
+        if self.fsync == FsyncPolicy::OnFlush {
+            if let Some(writer) = &self.writer {
+                writer.sync_data()?;
+            }
+        }
+        if self.segments.is_empty() || self.replay().is_ok() {
+            self.sender.flush()?;
+        }
+        Ok(())
+    }
+
+    fn context(&self) -> &SyslogContext {
+        &self.context
+    }
+
+    fn context_mut(&mut self) -> &mut SyslogContext {
+        &mut self.context
+    }
+}
+
+/// Returns the record at the given offset and the offset of the next one.
+///
+/// A truncated record, left by a crash while spooling, is ignored.
+fn next_record(data: &[u8], offset: usize) -> Option<(&[u8], usize)> {
+    let header = data.get(offset..offset + 4)?;
+    let len = u32::from_be_bytes(header.try_into().ok()?) as usize;
+    let start = offset + 4;
+    let record = data.get(start..start + len)?;
+    Some((record, start + len))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sender::testing::Recorder;
+
+    #[test]
+    fn test_spool_survives_restart() {
+        let dir = std::env::temp_dir().join(format!("fasyslog-spool-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+
+        let collector = Recorder::default();
+        let mut sender = SpoolSender::new(collector.to_sender(), &dir).unwrap();
+        sender.set_segment_size(16);
+        sender.send_formatted(b"first").unwrap();
+        collector.set_down(true);
+        for message in [b"second", b"third!", b"fourth"] {
+            sender.send_formatted(message).unwrap();
+        }
+        sender.flush().unwrap();
+        assert_eq!(sender.spooled_size(), 3 * 10);
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 3);
+        drop(sender);
+
+        collector.set_down(false);
+        let mut sender = SpoolSender::new(collector.to_sender(), &dir).unwrap();
+        sender.send_formatted(b"fifth").unwrap();
+        assert!(sender.is_empty());
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 0);
+
+        let expected = ["first", "second", "third!", "fourth", "fifth"];
+        assert_eq!(collector.sent(), expected);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,162 @@
+// Copyright 2024 FastLabs Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::borrow::Cow;
+use std::io;
+use std::io::Write;
+
+use crate::format::SdDaemonFormat;
+use crate::format::SyslogContext;
+use crate::sender::Sender;
+
+/// Create a sender that writes messages to the standard output of the process.
+///
+/// See [`StdioSender`] for details.
+pub fn stdout() -> StdioSender {
+    StdioSender::new(StdioWriter::Stdout(io::stdout()))
+}
+
+/// Create a sender that writes messages to the standard error of the process.
+///
+/// See [`StdioSender`] for details.
+pub fn stderr() -> StdioSender {
+    StdioSender::new(StdioWriter::Stderr(io::stderr()))
+}
+
+/// A syslog sender that writes messages to the standard output or standard error.
+///
+/// This is the lightest way for systemd services, whose stdout and stderr are connected to the
+/// journal, to log with proper severities. The default format of the sender is
+/// [`SdDaemonFormat`], so that [`send`](Sender::send) writes lines like `<3>message`, and every
+/// message is terminated by "\n".
+///
+/// Users can obtain a `StdioSender` by calling [`stdout`] or [`stderr`].
+#[derive(Debug)]
+pub struct StdioSender {
+    writer: StdioWriter,
+    context: SyslogContext,
+    postfix: Cow<'static, str>,
+}
+
+impl StdioSender {
+    fn new(writer: StdioWriter) -> Self {
+        let mut context = SyslogContext::default();
+        context.format(SdDaemonFormat::new());
+        Self {
+            writer,
+            context,
+            postfix: Cow::Borrowed("\n"),
+        }
+    }
+
+    /// Set the postfix when formatting Syslog message.
+    ///
+    /// Default is "\n".
+    pub fn set_postfix(&mut self, postfix: impl Into<Cow<'static, str>>) {
+        self.postfix = postfix.into();
+    }
+
+    /// Set the context when formatting Syslog message.
+    ///
+    /// Note that this replaces the default format of the sender as well.
+    pub fn set_context(&mut self, context: SyslogContext) {
+        self.context = context;
+    }
+
+    /// Mutate the context when formatting Syslog message.
+    pub fn mut_context(&mut self) -> &mut SyslogContext {
+        &mut self.context
+    }
+}
+
+impl Sender for StdioSender {
+    fn send_formatted(&mut self, formatted: &[u8]) -> io::Result<()> {
+        self.writer.write_all(formatted)?;
+        self.writer.write_all(self.postfix.as_bytes())?;
+        Ok(())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.writer.flush()
+    }
+
+    fn context(&self) -> &SyslogContext {
+        &self.context
+    }
+
+    fn context_mut(&mut self) -> &mut SyslogContext {
+        &mut self.context
+    }
+}
+
+#[derive(Debug)]
+enum StdioWriter {
+    Stdout(io::Stdout),
+    Stderr(io::Stderr),
+    #[cfg(test)]
+    Buffer(Vec<u8>),
+}
+
+impl Write for StdioWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match self {
+            StdioWriter::Stdout(stdout) => stdout.write(buf),
+            StdioWriter::Stderr(stderr) => stderr.write(buf),
+            #[cfg(test)]
+            StdioWriter::Buffer(buffer) => buffer.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match self {
+            StdioWriter::Stdout(stdout) => stdout.flush(),
+            StdioWriter::Stderr(stderr) => stderr.flush(),
+            #[cfg(test)]
+            StdioWriter::Buffer(_) => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::format::Multiline;
+    use crate::Facility;
+    use crate::Severity;
+
+    #[test]
+    fn test_stdio_targets() {
+        assert!(matches!(stdout().writer, StdioWriter::Stdout(_)));
+        assert!(matches!(stderr().writer, StdioWriter::Stderr(_)));
+    }
+
+    #[test]
+    fn test_prefix_every_line() {
+        let mut sender = StdioSender::new(StdioWriter::Buffer(Vec::new()));
+        sender.send(Severity::WARNING, "a\nb").unwrap();
+        sender.mut_context().multiline(Multiline::Split);
+        sender.send(Severity::ERROR, "c\nd").unwrap();
+        sender
+            .mut_context()
+            .facility(Facility::LOCAL0)
+            .format(SdDaemonFormat::new().with_facility(true));
+        sender.send(Severity::ERROR, "e").unwrap();
+
+        let StdioWriter::Buffer(output) = &sender.writer else {
+            unreachable!()
+        };
+        let output = String::from_utf8_lossy(output);
+        assert_eq!(output, "<4>a\n<4>b\n<3>c\n<3>d\n<131>e\n");
+    }
+}
@@ -17,10 +17,12 @@ use std::io;
 use std::io::BufWriter;
 use std::net::TcpStream;
 use std::net::ToSocketAddrs;
+use std::time::Duration;
 
 use crate::format::SyslogContext;
-use crate::sender::internal::impl_syslog_sender_common;
-use crate::sender::internal::impl_syslog_stream_send_formatted;
+use crate::sender::internal::impl_syslog_stream_sender;
+use crate::sender::Framing;
+use crate::sender::SocketOptions;
 
 /// Create a TCP sender that sends messages to the well-known port (601).
 ///
@@ -38,23 +40,63 @@ pub fn tcp<A: ToSocketAddrs>(addr: A) -> io::Result<TcpSender> {
 
 /// A syslog sender that sends messages to a TCP socket.
 ///
-/// Users can obtain a `TcpSender` by calling [`tcp_well_known`] or [`tcp`].
+/// Users can obtain a `TcpSender` by calling [`tcp_well_known`] or [`tcp`], or configure the socket
+/// with [`TcpSender::connect_with`].
 #[derive(Debug)]
 pub struct TcpSender {
     writer: BufWriter<TcpStream>,
     context: SyslogContext,
     postfix: Cow<'static, str>,
+    framing: Framing,
+    // set when a write timed out, since part of a message may have been written
+    poisoned: bool,
 }
 
 impl TcpSender {
     /// Connect to a TCP socket at the given address.
     pub fn connect<A: ToSocketAddrs>(addr: A) -> io::Result<Self> {
         let stream = TcpStream::connect(addr)?;
-        Ok(Self {
+        Ok(Self::new(stream))
+    }
+
+    /// Connect to a TCP socket at the given address, waiting at most `timeout` for every
+    /// resolved address in turn.
+    ///
+    /// Returns an error of kind [`io::ErrorKind::TimedOut`] if the last address timed out.
+    pub fn connect_timeout<A: ToSocketAddrs>(addr: A, timeout: Duration) -> io::Result<Self> {
+        Self::connect_with(addr, &SocketOptions::new().connect_timeout(timeout))
+    }
+
+    /// Connect to a TCP socket at the given address, with the given socket options.
+    pub fn connect_with<A: ToSocketAddrs>(addr: A, options: &SocketOptions) -> io::Result<Self> {
+        let stream = options.connect_tcp(addr)?;
+        Ok(Self::new(stream))
+    }
+
+    /// Create a new TCP sender with the given stream.
+    ///
+    /// This is useful when users want to configure the socket in fine-grained. Note that the
+    /// passed `stream` MUST be connected to the remote address.
+    pub fn new(stream: TcpStream) -> Self {
+        Self {
             writer: BufWriter::new(stream),
             context: SyslogContext::default(),
             postfix: Cow::Borrowed("\r\n"),
-        })
+            framing: Framing::NonTransparent,
+            poisoned: false,
+        }
+    }
+
+    /// Set the write timeout of the underlying socket.
+    ///
+    /// Sending or flushing then returns an error of kind [`io::ErrorKind::TimedOut`] instead of
+    /// blocking forever on a stalled server. Default is `None`, which blocks indefinitely.
+    ///
+    /// Part of a message may have been sent when the timeout expires, so later sends and flushes
+    /// fail with [`io::ErrorKind::BrokenPipe`]. Wrap the sender in a
+    /// [`ReconnectingSender`](crate::sender::ReconnectingSender) to connect again.
+    pub fn set_write_timeout(&mut self, timeout: Option<Duration>) -> io::Result<()> {
+        self.writer.get_ref().set_write_timeout(timeout)
     }
 
     /// Set the postfix when formatting Syslog message.
@@ -66,6 +108,13 @@ impl TcpSender {
         self.postfix = postfix.into();
     }
 
+    /// Set the framing of messages sent over the stream.
+    ///
+    /// Default is [`Framing::NonTransparent`], which terminates messages with the postfix.
+    pub fn set_framing(&mut self, framing: Framing) {
+        self.framing = framing;
+    }
+
     /// Set the context when formatting Syslog message.
     pub fn set_context(mut self, context: SyslogContext) {
         self.context = context;
@@ -77,5 +126,41 @@ impl TcpSender {
     }
 }
 
-impl_syslog_sender_common!(TcpSender);
-impl_syslog_stream_send_formatted!(TcpSender);
+impl_syslog_stream_sender!(TcpSender);
+
+#[cfg(test)]
+mod tests {
+    use std::net::SocketAddr;
+    use std::net::TcpListener;
+
+    use super::*;
+    use crate::sender::Sender;
+
+    #[test]
+    fn test_write_timeout() {
+        let no_addrs: &[SocketAddr] = &[];
+        let err = TcpSender::connect_timeout(no_addrs, Duration::from_secs(1)).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let mut sender = TcpSender::connect_timeout(addr, Duration::from_secs(1)).unwrap();
+        // accept, but never read
+        let _stream = listener.accept().unwrap();
+        sender
+            .set_write_timeout(Some(Duration::from_millis(50)))
+            .unwrap();
+        let message = vec![b'x'; 64 * 1024];
+        let err = (0..10_000)
+            .find_map(|_| sender.send_formatted(&message).err())
+            .expect("the socket buffers never filled up");
+        assert_eq!(err.kind(), io::ErrorKind::TimedOut);
+        assert_eq!(err.to_string(), "write timed out");
+
+        // part of the message may be on the wire, so the stream cannot be reused
+        let err = sender.send_formatted(b"next").unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::BrokenPipe, "{err}");
+        let err = sender.flush().unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::BrokenPipe, "{err}");
+    }
+}
@@ -0,0 +1,186 @@
+// Copyright 2024 FastLabs Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Test helpers of the senders.
+
+use std::io;
+use std::sync::atomic::AtomicBool;
+use std::sync::atomic::Ordering;
+use std::sync::mpsc;
+use std::sync::Arc;
+use std::sync::Mutex;
+
+use crate::format::SyslogContext;
+use crate::sender::Sender;
+use crate::sender::SyslogSender;
+
+/// A self-signed certificate for `localhost` and `127.0.0.1`, in PEM.
+#[cfg(any(feature = "native-tls", feature = "rustls"))]
+pub(crate) const CERT: &[u8] = include_bytes!("testdata/localhost.crt");
+
+/// The PKCS #8 private key of [`CERT`], in PEM.
+#[cfg(any(feature = "native-tls", feature = "rustls"))]
+pub(crate) const KEY: &[u8] = include_bytes!("testdata/localhost.key");
+
+/// A sender that records the messages it is sent.
+///
+/// Clones share the recorded messages and the state, so that a test can keep a clone to inspect a
+/// recorder that was moved into the sender under test.
+#[derive(Debug, Default, Clone)]
+pub(crate) struct Recorder {
+    context: SyslogContext,
+    down: Arc<AtomicBool>,
+    gate: Option<(Arc<Mutex<()>>, mpsc::Sender<()>)>,
+    // the messages sent but not flushed yet, if the recorder is buffered
+    buffer: Option<Arc<Mutex<Vec<Vec<u8>>>>>,
+    sent: Arc<Mutex<Vec<Vec<u8>>>>,
+}
+
+impl Recorder {
+    /// Create a recorder that signals `entered` before every send, and then blocks until `gate`
+    /// is unlocked.
+    pub(crate) fn gated(gate: Arc<Mutex<()>>, entered: mpsc::Sender<()>) -> Self {
+        Self {
+            gate: Some((gate, entered)),
+            ..Self::default()
+        }
+    }
+
+    /// Create a recorder that only records messages when it is flushed, like a stream sender.
+    pub(crate) fn buffered() -> Self {
+        Self {
+            buffer: Some(Arc::default()),
+            ..Self::default()
+        }
+    }
+
+    /// Make every send fail with [`io::ErrorKind::ConnectionRefused`] while `down` is `true`.
+    pub(crate) fn set_down(&self, down: bool) {
+        self.down.store(down, Ordering::SeqCst);
+    }
+
+    /// Returns the messages recorded so far.
+    pub(crate) fn sent(&self) -> Vec<String> {
+        let sent = self.sent.lock().unwrap();
+        sent.iter()
+            .map(|formatted| String::from_utf8_lossy(formatted).into_owned())
+            .collect()
+    }
+
+    /// Returns a clone of the recorder as a [`SyslogSender::Custom`].
+    pub(crate) fn to_sender(&self) -> SyslogSender {
+        SyslogSender::Custom(Box::new(self.clone()))
+    }
+}
+
+impl Sender for Recorder {
+    fn send_formatted(&mut self, formatted: &[u8]) -> io::Result<()> {
+        if let Some((gate, entered)) = &self.gate {
+            entered.send(()).unwrap();
+            let _gate = gate.lock().unwrap();
+        }
+        if self.down.load(Ordering::SeqCst) {
+            return Err(io::ErrorKind::ConnectionRefused.into());
+        }
+        let sent = self.buffer.as_ref().unwrap_or(&self.sent);
+        sent.lock().unwrap().push(formatted.to_vec());
+        Ok(())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        if let Some(buffer) = &self.buffer {
+            let mut buffer = buffer.lock().unwrap();
+            self.sent.lock().unwrap().append(&mut buffer);
+        }
+        Ok(())
+    }
+
+    fn context(&self) -> &SyslogContext {
+        &self.context
+    }
+
+    fn context_mut(&mut self) -> &mut SyslogContext {
+        &mut self.context
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+    use std::time::Duration;
+
+    use super::*;
+    use crate::sender::AsyncQueueSender;
+    use crate::sender::DedupSender;
+    use crate::sender::FailoverSender;
+    use crate::sender::FanoutDestination;
+    use crate::sender::FanoutSender;
+    use crate::sender::RateLimit;
+    use crate::sender::RateLimitedSender;
+    use crate::sender::ReconnectingSender;
+    use crate::sender::SpoolSender;
+    use crate::Facility;
+    use crate::Severity;
+
+    #[test]
+    fn test_wrappers_inherit_context() {
+        type Wrap = fn(Recorder, &Path) -> Box<dyn Sender + Send>;
+        let wrappers: [(&str, Wrap); 7] = [
+            ("reconnect", |recorder, _| {
+                Box::new(ReconnectingSender::new(move || Ok(recorder.clone())).unwrap())
+            }),
+            ("failover", |recorder, _| {
+                Box::new(FailoverSender::new(vec![recorder.to_sender()]))
+            }),
+            ("fanout", |recorder, _| {
+                Box::new(FanoutSender::new(vec![
+                    FanoutDestination::new(recorder.to_sender()),
+                    FanoutDestination::new(Recorder::default().to_sender()),
+                ]))
+            }),
+            ("queue", |recorder, _| {
+                Box::new(AsyncQueueSender::new(recorder, 8).unwrap())
+            }),
+            ("spool", |recorder, dir| {
+                Box::new(SpoolSender::new(recorder.to_sender(), dir).unwrap())
+            }),
+            ("ratelimit", |recorder, _| {
+                let limit = RateLimit::new(10, Duration::from_secs(1));
+                Box::new(RateLimitedSender::new(recorder, limit))
+            }),
+            ("dedup", |recorder, _| Box::new(DedupSender::new(recorder))),
+        ];
+
+        let dir = std::env::temp_dir().join(format!("fasyslog-inherit-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        for (name, wrap) in wrappers {
+            let mut recorder = Recorder::default();
+            recorder
+                .context_mut()
+                .facility(Facility::LOCAL1)
+                .appname("inner");
+            let mut sender = wrap(recorder.clone(), &dir);
+            assert_eq!(sender.context().get_facility(), Facility::LOCAL1, "{name}");
+            assert_eq!(sender.context().get_appname(), Some("inner"), "{name}");
+
+            sender.send_rfc3164(Severity::ERROR, "hello").unwrap();
+            sender.flush().unwrap();
+            let sent = recorder.sent();
+            assert_eq!(sent.len(), 1, "{name}: {sent:?}");
+            assert!(sent[0].starts_with("<139>"), "{name}: {}", sent[0]);
+            assert!(sent[0].contains(" inner["), "{name}: {}", sent[0]);
+        }
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
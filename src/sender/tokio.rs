@@ -0,0 +1,147 @@
+// Copyright 2024 FastLabs Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Send syslog messages to a syslog server from async code running on [tokio].
+//!
+//! The senders in this module mirror the blocking senders of [`crate::sender`]. Messages are
+//! formatted when a `send_xxx` method is called, and the returned future only writes the
+//! formatted bytes, so it does not borrow the message.
+//!
+//! [tokio]: https://tokio.rs
+
+#[cfg(unix)]
+mod unix;
+#[cfg(unix)]
+pub use unix::*;
+
+#[cfg(feature = "tokio-native-tls")]
+mod native_tls;
+#[cfg(feature = "tokio-native-tls")]
+pub use native_tls::*;
+
+mod tcp;
+pub use tcp::*;
+
+mod udp;
+pub use udp::*;
+
+macro_rules! impl_async_sender {
+    ($sender:ident) => {
+        impl $sender {
+            /// Returns the context when formatting Syslog message.
+            pub fn context(&self) -> &$crate::format::SyslogContext {
+                &self.context
+            }
+
+            /// Send a message with the given severity in the given format.
+            ///
+            /// See also [`Sender::send_message`]($crate::sender::Sender::send_message).
+            pub fn send_message(
+                &mut self,
+                format: &dyn $crate::format::SyslogFormat,
+                severity: $crate::Severity,
+                message: &dyn std::fmt::Display,
+            ) -> impl std::future::Future<Output = std::io::Result<()>> + '_ {
+                let formatted = self.context.format_message(format, severity, message);
+                async move {
+                    for formatted in formatted? {
+                        self.send_formatted(&formatted).await?;
+                    }
+                    Ok(())
+                }
+            }
+
+            /// Send a message with the given severity in the given format.
+            pub fn send_with<M: std::fmt::Display>(
+                &mut self,
+                format: &dyn $crate::format::SyslogFormat,
+                severity: $crate::Severity,
+                message: M,
+            ) -> impl std::future::Future<Output = std::io::Result<()>> + '_ {
+                self.send_message(format, severity, &message)
+            }
+
+            /// Send a message with the given severity in the default format of the context.
+            ///
+            /// See also [`SyslogContext::format`]($crate::format::SyslogContext::format).
+            pub fn send<M: std::fmt::Display>(
+                &mut self,
+                severity: $crate::Severity,
+                message: M,
+            ) -> impl std::future::Future<Output = std::io::Result<()>> + '_ {
+                match self.context.default_format() {
+                    Some(format) => self.send_message(&*format, severity, &message),
+                    None => self.send_message(&$crate::format::RFC3164Format, severity, &message),
+                }
+            }
+
+            /// Send a message with the given severity as defined in RFC-3164.
+            pub fn send_rfc3164<M: std::fmt::Display>(
+                &mut self,
+                severity: $crate::Severity,
+                message: M,
+            ) -> impl std::future::Future<Output = std::io::Result<()>> + '_ {
+                self.send_message(&$crate::format::RFC3164Format, severity, &message)
+            }
+
+            /// Send a message with the given severity as defined in RFC-5424.
+            pub fn send_rfc5424<S: Into<String>, M: std::fmt::Display>(
+                &mut self,
+                severity: $crate::Severity,
+                msgid: Option<S>,
+                elements: Vec<$crate::SDElement>,
+                message: M,
+            ) -> impl std::future::Future<Output = std::io::Result<()>> + '_ {
+                let format = $crate::format::RFC5424Format::new(msgid, elements);
+                self.send_message(&format, severity, &message)
+            }
+        }
+    };
+}
+
+macro_rules! impl_async_stream_sender {
+    ($sender:ident) => {
+        impl $sender {
+            /// Send a pre-formatted message.
+            pub async fn send_formatted(&mut self, formatted: &[u8]) -> std::io::Result<()> {
+                use tokio::io::AsyncWriteExt;
+                match self.framing {
+                    $crate::sender::Framing::OctetCounting => {
+                        let len = format!("{} ", formatted.len());
+                        self.writer.write_all(len.as_bytes()).await?;
+                        self.writer.write_all(formatted).await?;
+                    }
+                    $crate::sender::Framing::NonTransparent => {
+                        self.writer.write_all(formatted).await?;
+                        self.writer.write_all(self.postfix.as_bytes()).await?;
+                    }
+                }
+                Ok(())
+            }
+
+            /// Flush the underlying writer.
+            ///
+            /// See also [`Sender::flush`]($crate::sender::Sender::flush).
+            pub async fn flush(&mut self) -> std::io::Result<()> {
+                use tokio::io::AsyncWriteExt;
+                self.writer.flush().await
+            }
+        }
+
+        $crate::sender::tokio::impl_async_sender!($sender);
+    };
+}
+
+pub(crate) use impl_async_sender;
+pub(crate) use impl_async_stream_sender;
@@ -0,0 +1,166 @@
+// Copyright 2024 FastLabs Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::borrow::Cow;
+use std::io;
+
+use native_tls::TlsConnectorBuilder;
+use tokio::io::BufWriter;
+use tokio::net::TcpStream;
+use tokio::net::ToSocketAddrs;
+use tokio_native_tls::TlsConnector;
+use tokio_native_tls::TlsStream;
+
+use crate::format::SyslogContext;
+use crate::sender::tokio::impl_async_stream_sender;
+use crate::sender::Framing;
+
+/// Create a TLS sender that sends messages to the well-known port (6514).
+///
+/// See also [RFC-5425] §4.1 Port Assignment.
+///
+/// [RFC-5425]: https://datatracker.ietf.org/doc/html/rfc5425#section-4.1
+pub async fn native_tls_well_known<S: AsRef<str>>(domain: S) -> io::Result<NativeTlsSender> {
+    let domain = domain.as_ref();
+    native_tls(format!("{domain}:6514"), domain).await
+}
+
+/// Create a TLS sender that sends messages to the given address.
+pub async fn native_tls<A: ToSocketAddrs, S: AsRef<str>>(
+    addr: A,
+    domain: S,
+) -> io::Result<NativeTlsSender> {
+    native_tls_with(addr, domain, native_tls::TlsConnector::builder()).await
+}
+
+/// Create a TLS sender that sends messages to the given address with certificate builder.
+pub async fn native_tls_with<A: ToSocketAddrs, S: AsRef<str>>(
+    addr: A,
+    domain: S,
+    builder: TlsConnectorBuilder,
+) -> io::Result<NativeTlsSender> {
+    NativeTlsSender::connect(addr, domain, builder).await
+}
+
+/// An async syslog sender that sends messages to a TCP socket over TLS.
+///
+/// Users can obtain a `NativeTlsSender` by calling [`native_tls_well_known`], [`native_tls`], or
+/// [`native_tls_with`].
+#[derive(Debug)]
+pub struct NativeTlsSender {
+    writer: BufWriter<TlsStream<TcpStream>>,
+    context: SyslogContext,
+    postfix: Cow<'static, str>,
+    framing: Framing,
+}
+
+impl NativeTlsSender {
+    /// Connect to a TCP socket over TLS at the given address.
+    pub async fn connect<A: ToSocketAddrs, S: AsRef<str>>(
+        addr: A,
+        domain: S,
+        builder: TlsConnectorBuilder,
+    ) -> io::Result<Self> {
+        let stream = TcpStream::connect(addr).await?;
+        let connector = builder.build().map_err(io::Error::other)?;
+        let stream = TlsConnector::from(connector)
+            .connect(domain.as_ref(), stream)
+            .await
+            .map_err(io::Error::other)?;
+        Ok(Self {
+            writer: BufWriter::new(stream),
+            context: SyslogContext::default(),
+            postfix: Cow::Borrowed("\r\n"),
+            framing: Framing::NonTransparent,
+        })
+    }
+
+    /// Set the postfix when formatting Syslog message.
+    ///
+    /// This is generally '\r\n' as defined in [RFC-6587] §3.4.2.
+    ///
+    /// [RFC-6587]: https://datatracker.ietf.org/doc/html/rfc6587
+    pub fn set_postfix(&mut self, postfix: impl Into<Cow<'static, str>>) {
+        self.postfix = postfix.into();
+    }
+
+    /// Set the framing of messages sent over the stream.
+    ///
+    /// Default is [`Framing::NonTransparent`], which terminates messages with the postfix.
+    pub fn set_framing(&mut self, framing: Framing) {
+        self.framing = framing;
+    }
+
+    /// Set the context when formatting Syslog message.
+    pub fn set_context(&mut self, context: SyslogContext) {
+        self.context = context;
+    }
+
+    /// Mutate the context when formatting Syslog message.
+    pub fn mut_context(&mut self) -> &mut SyslogContext {
+        &mut self.context
+    }
+}
+
+impl_async_stream_sender!(NativeTlsSender);
+
+#[cfg(test)]
+mod tests {
+    use native_tls::Certificate;
+    use native_tls::Identity;
+    use tokio::io::AsyncReadExt;
+    use tokio::net::TcpListener;
+
+    use super::*;
+    use crate::sender::testing::CERT;
+    use crate::sender::testing::KEY;
+    use crate::Severity;
+
+    #[tokio::test]
+    async fn test_send_over_tls() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let identity = Identity::from_pkcs8(CERT, KEY).unwrap();
+        let acceptor =
+            tokio_native_tls::TlsAcceptor::from(native_tls::TlsAcceptor::new(identity).unwrap());
+        let server = tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            let mut stream = acceptor.accept(stream).await.unwrap();
+            let mut received = Vec::new();
+            let mut buf = [0; 1024];
+            // the client may close the connection without a TLS close_notify
+            while let Ok(len @ 1..) = stream.read(&mut buf).await {
+                received.extend_from_slice(&buf[..len]);
+            }
+            String::from_utf8(received).unwrap()
+        });
+
+        let mut builder = native_tls::TlsConnector::builder();
+        builder.add_root_certificate(Certificate::from_pem(CERT).unwrap());
+        let mut sender = native_tls_with(addr, "localhost", builder).await.unwrap();
+        sender.set_framing(Framing::OctetCounting);
+        sender
+            .send_rfc3164(Severity::NOTICE, "Hello, syslog!")
+            .await
+            .unwrap();
+        sender.flush().await.unwrap();
+        drop(sender);
+
+        let received = server.await.unwrap();
+        let (len, message) = received.split_once(' ').unwrap();
+        assert_eq!(len.parse::<usize>().unwrap(), message.len());
+        assert!(message.starts_with("<13>"), "{message}");
+        assert!(message.ends_with("Hello, syslog!"), "{message}");
+    }
+}
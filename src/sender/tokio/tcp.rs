@@ -0,0 +1,125 @@
+// Copyright 2024 FastLabs Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::borrow::Cow;
+use std::io;
+
+use tokio::io::BufWriter;
+use tokio::net::TcpStream;
+use tokio::net::ToSocketAddrs;
+
+use crate::format::SyslogContext;
+use crate::sender::tokio::impl_async_stream_sender;
+use crate::sender::Framing;
+
+/// Create a TCP sender that sends messages to the well-known port (601).
+///
+/// See also [RFC-3195] §9.2 The System (Well-Known) TCP port number for syslog-conn.
+///
+/// [RFC-3195]: https://datatracker.ietf.org/doc/html/rfc3195#section-9.2
+pub async fn tcp_well_known() -> io::Result<TcpSender> {
+    tcp("127.0.0.1:601").await
+}
+
+/// Create a TCP sender that sends messages to the given address.
+pub async fn tcp<A: ToSocketAddrs>(addr: A) -> io::Result<TcpSender> {
+    TcpSender::connect(addr).await
+}
+
+/// An async syslog sender that sends messages to a TCP socket.
+///
+/// Users can obtain a `TcpSender` by calling [`tcp_well_known`] or [`tcp`].
+#[derive(Debug)]
+pub struct TcpSender {
+    writer: BufWriter<TcpStream>,
+    context: SyslogContext,
+    postfix: Cow<'static, str>,
+    framing: Framing,
+}
+
+impl TcpSender {
+    /// Connect to a TCP socket at the given address.
+    pub async fn connect<A: ToSocketAddrs>(addr: A) -> io::Result<Self> {
+        let stream = TcpStream::connect(addr).await?;
+        Ok(Self {
+            writer: BufWriter::new(stream),
+            context: SyslogContext::default(),
+            postfix: Cow::Borrowed("\r\n"),
+            framing: Framing::NonTransparent,
+        })
+    }
+
+    /// Set the postfix when formatting Syslog message.
+    ///
+    /// This is generally '\r\n' as defined in [RFC-6587] §3.4.2.
+    ///
+    /// [RFC-6587]: https://datatracker.ietf.org/doc/html/rfc6587
+    pub fn set_postfix(&mut self, postfix: impl Into<Cow<'static, str>>) {
+        self.postfix = postfix.into();
+    }
+
+    /// Set the framing of messages sent over the stream.
+    ///
+    /// Default is [`Framing::NonTransparent`], which terminates messages with the postfix.
+    pub fn set_framing(&mut self, framing: Framing) {
+        self.framing = framing;
+    }
+
+    /// Set the context when formatting Syslog message.
+    pub fn set_context(&mut self, context: SyslogContext) {
+        self.context = context;
+    }
+
+    /// Mutate the context when formatting Syslog message.
+    pub fn mut_context(&mut self) -> &mut SyslogContext {
+        &mut self.context
+    }
+}
+
+impl_async_stream_sender!(TcpSender);
+
+#[cfg(test)]
+mod tests {
+    use tokio::io::AsyncReadExt;
+    use tokio::net::TcpListener;
+
+    use super::*;
+    use crate::Severity;
+
+    #[tokio::test]
+    async fn test_send_over_tcp() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let mut sender = tcp(listener.local_addr().unwrap()).await.unwrap();
+        sender.set_framing(Framing::OctetCounting);
+        let (mut stream, _) = listener.accept().await.unwrap();
+
+        // the futures are Send, and do not borrow the message
+        tokio::spawn(async move {
+            let message = String::from("Hello, syslog!");
+            let send = sender.send_rfc3164(Severity::NOTICE, &message);
+            drop(message);
+            send.await.unwrap();
+            sender.flush().await.unwrap();
+        })
+        .await
+        .unwrap();
+
+        let mut received = String::new();
+        stream.read_to_string(&mut received).await.unwrap();
+        let (len, message) = received.split_once(' ').unwrap();
+        assert_eq!(len.parse::<usize>().unwrap(), message.len());
+        assert!(message.starts_with("<13>"), "{message}");
+        assert!(message.ends_with("Hello, syslog!"), "{message}");
+    }
+}
@@ -0,0 +1,135 @@
+// Copyright 2024 FastLabs Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::io;
+
+use tokio::net::ToSocketAddrs;
+use tokio::net::UdpSocket;
+
+use crate::format::SyslogContext;
+use crate::sender::tokio::impl_async_sender;
+
+/// Create a UDP sender that sends messages to the well-known port (514).
+///
+/// See also [RFC-3164] §2 Transport Layer Protocol.
+///
+/// [RFC-3164]: https://datatracker.ietf.org/doc/html/rfc3164#section-2
+pub async fn udp_well_known() -> io::Result<UdpSender> {
+    udp("0.0.0.0:0", "127.0.0.1:514").await
+}
+
+/// Create a UDP sender that sends messages to the given address.
+pub async fn udp<L: ToSocketAddrs, R: ToSocketAddrs>(local: L, remote: R) -> io::Result<UdpSender> {
+    UdpSender::connect(local, remote).await
+}
+
+/// Create a UDP sender that broadcast messages to the well-known port (514).
+///
+/// See also [RFC-3164] §2 Transport Layer Protocol.
+///
+/// [RFC-3164]: https://datatracker.ietf.org/doc/html/rfc3164#section-2
+pub async fn broadcast_well_known() -> io::Result<UdpSender> {
+    broadcast(514).await
+}
+
+/// Create a UDP sender that broadcast messages to the given port.
+pub async fn broadcast(port: u16) -> io::Result<UdpSender> {
+    let socket = UdpSocket::bind("0.0.0.0:0").await?;
+    socket.set_broadcast(true)?;
+    socket.connect(format!("255.255.255.255:{port}")).await?;
+    Ok(UdpSender::new(socket))
+}
+
+/// An async syslog sender that sends messages to a UDP socket.
+///
+/// Users can obtain a `UdpSender` by calling [`udp_well_known`], [`udp`], [`broadcast_well_known`],
+/// or [`broadcast`].
+#[derive(Debug)]
+pub struct UdpSender {
+    socket: UdpSocket,
+    context: SyslogContext,
+}
+
+impl UdpSender {
+    /// Connect to a UDP socket at the given address.
+    pub async fn connect<L: ToSocketAddrs, R: ToSocketAddrs>(
+        local: L,
+        remote: R,
+    ) -> io::Result<Self> {
+        let socket = UdpSocket::bind(local).await?;
+        socket.connect(remote).await?;
+        Ok(Self::new(socket))
+    }
+
+    /// Create a new UDP sender with the given socket.
+    ///
+    /// Note that the passed `socket` MUST be connected to the remote address.
+    pub fn new(socket: UdpSocket) -> Self {
+        Self {
+            socket,
+            context: SyslogContext::default(),
+        }
+    }
+
+    /// Set the context when formatting Syslog message.
+    pub fn set_context(&mut self, context: SyslogContext) {
+        self.context = context;
+    }
+
+    /// Mutate the context when formatting Syslog message.
+    pub fn mut_context(&mut self) -> &mut SyslogContext {
+        &mut self.context
+    }
+
+    /// Send a pre-formatted message.
+    pub async fn send_formatted(&mut self, formatted: &[u8]) -> io::Result<()> {
+        self.socket.send(formatted).await?;
+        Ok(())
+    }
+
+    /// Flush is a no-op for datagram sockets.
+    pub async fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl_async_sender!(UdpSender);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Severity;
+
+    #[tokio::test]
+    async fn test_send_over_udp() {
+        let receiver = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let mut sender = udp("127.0.0.1:0", receiver.local_addr().unwrap())
+            .await
+            .unwrap();
+        sender.mut_context().appname("udp-test");
+
+        let message = String::from("Hello, syslog!");
+        let send = sender.send_rfc5424(Severity::WARNING, None::<String>, Vec::new(), &message);
+        drop(message);
+        send.await.unwrap();
+        sender.flush().await.unwrap();
+
+        let mut buf = [0; 1024];
+        let len = receiver.recv(&mut buf).await.unwrap();
+        let message = std::str::from_utf8(&buf[..len]).unwrap();
+        assert!(message.starts_with("<12>1 "), "{message}");
+        assert!(message.contains(" udp-test "), "{message}");
+        assert!(message.ends_with("Hello, syslog!"), "{message}");
+    }
+}
@@ -0,0 +1,202 @@
+// Copyright 2024 FastLabs Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::borrow::Cow;
+use std::io;
+use std::path::Path;
+
+use tokio::io::BufWriter;
+use tokio::net::UnixDatagram;
+use tokio::net::UnixStream;
+
+use crate::format::SyslogContext;
+use crate::sender::tokio::impl_async_sender;
+use crate::sender::tokio::impl_async_stream_sender;
+use crate::sender::Framing;
+
+/// Create a Unix datagram sender that sends messages to the given path.
+pub fn unix_datagram(path: impl AsRef<Path>) -> io::Result<UnixDatagramSender> {
+    UnixDatagramSender::connect(path)
+}
+
+/// Create a Unix stream sender that sends messages to the given path.
+pub async fn unix_stream(path: impl AsRef<Path>) -> io::Result<UnixStreamSender> {
+    UnixStreamSender::connect(path).await
+}
+
+/// An async syslog sender that sends messages to a Unix datagram socket.
+///
+/// Typically, this sender is used to send messages to the local syslog daemon. Typical paths are
+/// `/dev/log`, `/var/run/syslog`, or `/var/run/log`.
+#[derive(Debug)]
+pub struct UnixDatagramSender {
+    socket: UnixDatagram,
+    context: SyslogContext,
+}
+
+impl UnixDatagramSender {
+    /// Connect to a Unix datagram socket at the given path.
+    ///
+    /// This must be called within a tokio runtime.
+    pub fn connect(path: impl AsRef<Path>) -> io::Result<Self> {
+        let socket = UnixDatagram::unbound()?;
+        socket.connect(path)?;
+        Ok(Self {
+            socket,
+            context: SyslogContext::default(),
+        })
+    }
+
+    /// Set the context when formatting Syslog message.
+    pub fn set_context(&mut self, context: SyslogContext) {
+        self.context = context;
+    }
+
+    /// Mutate the context when formatting Syslog message.
+    pub fn mut_context(&mut self) -> &mut SyslogContext {
+        &mut self.context
+    }
+
+    /// Send a pre-formatted message.
+    pub async fn send_formatted(&mut self, formatted: &[u8]) -> io::Result<()> {
+        self.socket.send(formatted).await?;
+        Ok(())
+    }
+
+    /// Flush is a no-op for datagram sockets.
+    pub async fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl_async_sender!(UnixDatagramSender);
+
+/// An async syslog sender that sends messages to a Unix stream socket.
+///
+/// Typically, this sender is used to send messages to the local syslog daemon. Typical paths are
+/// `/dev/log`, `/var/run/syslog`, or `/var/run/log`.
+#[derive(Debug)]
+pub struct UnixStreamSender {
+    writer: BufWriter<UnixStream>,
+    context: SyslogContext,
+    postfix: Cow<'static, str>,
+    framing: Framing,
+}
+
+impl UnixStreamSender {
+    /// Connect to a Unix stream socket at the given path.
+    pub async fn connect(path: impl AsRef<Path>) -> io::Result<Self> {
+        let socket = UnixStream::connect(path).await?;
+        Ok(Self {
+            writer: BufWriter::new(socket),
+            context: SyslogContext::default(),
+            postfix: Cow::Borrowed("\r\n"),
+            framing: Framing::NonTransparent,
+        })
+    }
+
+    /// Set the postfix when formatting Syslog message.
+    ///
+    /// Default is "\r\n". You can use empty string to set no postfix, or "\0" to frame messages
+    /// as glibc's `syslog(3)` does.
+    pub fn set_postfix(&mut self, postfix: impl Into<Cow<'static, str>>) {
+        self.postfix = postfix.into();
+    }
+
+    /// Set the framing of messages sent over the stream.
+    ///
+    /// Default is [`Framing::NonTransparent`], which terminates messages with the postfix.
+    pub fn set_framing(&mut self, framing: Framing) {
+        self.framing = framing;
+    }
+
+    /// Set the context when formatting Syslog message.
+    pub fn set_context(&mut self, context: SyslogContext) {
+        self.context = context;
+    }
+
+    /// Mutate the context when formatting Syslog message.
+    pub fn mut_context(&mut self) -> &mut SyslogContext {
+        &mut self.context
+    }
+}
+
+impl_async_stream_sender!(UnixStreamSender);
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use tokio::io::AsyncReadExt;
+    use tokio::net::UnixListener;
+
+    use super::*;
+    use crate::format::Multiline;
+    use crate::Severity;
+
+    fn socket_path(name: &str) -> PathBuf {
+        let path = std::env::temp_dir().join(format!("fasyslog-{name}-{}", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        path
+    }
+
+    #[tokio::test]
+    async fn test_send_over_unix_datagram() {
+        let path = socket_path("tokio-dgram");
+        let receiver = UnixDatagram::bind(&path).unwrap();
+        let mut sender = unix_datagram(&path).unwrap();
+        sender.mut_context().multiline(Multiline::Split);
+
+        sender.send_rfc3164(Severity::ERROR, "a\nb").await.unwrap();
+        sender.flush().await.unwrap();
+
+        let mut buf = [0; 1024];
+        for expected in ["a", "b"] {
+            let len = receiver.recv(&mut buf).await.unwrap();
+            let message = std::str::from_utf8(&buf[..len]).unwrap();
+            assert!(message.starts_with("<11>"), "{message}");
+            assert!(message.ends_with(&format!(": {expected}")), "{message}");
+        }
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_send_over_unix_stream() {
+        let path = socket_path("tokio-stream");
+        let listener = UnixListener::bind(&path).unwrap();
+        let mut sender = unix_stream(&path).await.unwrap();
+        sender.set_postfix("\0");
+        let (mut stream, _) = listener.accept().await.unwrap();
+
+        sender
+            .send_rfc3164(Severity::INFORMATIONAL, "one")
+            .await
+            .unwrap();
+        sender
+            .send_rfc3164(Severity::INFORMATIONAL, "two")
+            .await
+            .unwrap();
+        sender.flush().await.unwrap();
+        drop(sender);
+
+        let mut received = String::new();
+        stream.read_to_string(&mut received).await.unwrap();
+        let messages = received.split_terminator('\0').collect::<Vec<_>>();
+        assert_eq!(messages.len(), 2, "{received:?}");
+        assert!(messages[0].starts_with("<14>"), "{received:?}");
+        assert!(messages[0].ends_with(": one"), "{received:?}");
+        assert!(messages[1].ends_with(": two"), "{received:?}");
+        std::fs::remove_file(&path).unwrap();
+    }
+}
@@ -17,7 +17,8 @@ use std::net::ToSocketAddrs;
 use std::net::UdpSocket;
 
 use crate::format::SyslogContext;
-use crate::sender::internal::impl_syslog_sender_common;
+use crate::sender::Sender;
+use crate::sender::SocketOptions;
 
 /// Create a UDP sender that sends messages to the well-known port (514).
 ///
@@ -53,7 +54,7 @@ pub fn broadcast(port: u16) -> io::Result<UdpSender> {
 /// A syslog sender that sends messages to a UDP socket.
 ///
 /// Users can obtain a `UdpSender` by calling [`udp_well_known`], [`udp`], [`broadcast_well_known`],
-/// or [`broadcast`].
+/// or [`broadcast`], or configure the socket with [`UdpSender::connect_with`].
 #[derive(Debug)]
 pub struct UdpSender {
     socket: UdpSocket,
@@ -68,6 +69,14 @@ impl UdpSender {
         Ok(Self::new(socket))
     }
 
+    /// Connect to a UDP socket at the given remote address, with the given socket options.
+    ///
+    /// The socket is bound to [`SocketOptions::bind`] if set, or to an ephemeral port otherwise.
+    pub fn connect_with<R: ToSocketAddrs>(remote: R, options: &SocketOptions) -> io::Result<Self> {
+        let socket = options.connect_udp(remote)?;
+        Ok(Self::new(socket))
+    }
+
     /// Create a new UDP sender with the given socket.
     ///
     /// This is useful when users want to configure the socket in fine-grained. Note that the
@@ -89,11 +98,26 @@ impl UdpSender {
         &mut self.context
     }
 
-    /// Send a pre-formatted message.
-    pub fn send_formatted(&mut self, formatted: &[u8]) -> io::Result<()> {
+    pub(crate) fn into_parts(self) -> (UdpSocket, SyslogContext) {
+        (self.socket, self.context)
+    }
+}
+
+impl Sender for UdpSender {
+    fn send_formatted(&mut self, formatted: &[u8]) -> io::Result<()> {
         self.socket.send(formatted)?;
         Ok(())
     }
-}
 
-impl_syslog_sender_common!(UdpSender);
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+
+    fn context(&self) -> &SyslogContext {
+        &self.context
+    }
+
+    fn context_mut(&mut self) -> &mut SyslogContext {
+        &mut self.context
+    }
+}
@@ -19,11 +19,18 @@ use std::os::unix::net::UnixDatagram;
 use std::os::unix::net::UnixStream;
 use std::path::Path;
 
+use crate::format::LocalFormat;
 use crate::format::SyslogContext;
-use crate::sender::internal::impl_syslog_sender_common;
-use crate::sender::internal::impl_syslog_stream_send_formatted;
+use crate::sender::internal::impl_syslog_stream_sender;
+use crate::sender::Framing;
+use crate::sender::Sender;
 use crate::sender::SyslogSender;
 
+#[cfg(target_os = "linux")]
+mod journald;
+#[cfg(target_os = "linux")]
+pub use journald::*;
+
 /// Create a Unix datagram sender that sends messages to the given path.
 pub fn unix_datagram(path: impl AsRef<Path>) -> io::Result<UnixDatagramSender> {
     UnixDatagramSender::connect(path)
@@ -49,16 +56,56 @@ pub fn unix(path: impl AsRef<Path>) -> io::Result<SyslogSender> {
     }
 }
 
+/// Create a Unix sender that sends messages to the well-known local syslog socket.
 pub fn unix_well_known() -> io::Result<SyslogSender> {
+    unix(well_known_path())
+}
+
+/// Create a Unix sender that sends messages to the given path the same way glibc's [syslog(3)]
+/// does.
+///
+/// The default format of the sender is [`LocalFormat`], so that [`send`](Sender::send)
+/// omits the hostname, and stream messages are terminated by a NUL byte instead of "\r\n".
+///
+/// Methods that name their format still use it: [`send_rfc3164`](Sender::send_rfc3164) includes
+/// the hostname, so use [`send`](Sender::send) or [`send_with`](Sender::send_with) with
+/// [`LocalFormat`] to format messages as glibc does.
+///
+/// [syslog(3)]: https://www.man7.org/linux/man-pages/man3/syslog.3.html
+pub fn unix_local(path: impl AsRef<Path>) -> io::Result<SyslogSender> {
+    let sender = match unix(path)? {
+        SyslogSender::UnixDatagram(mut sender) => {
+            sender.mut_context().format(LocalFormat);
+            SyslogSender::UnixDatagram(sender)
+        }
+        SyslogSender::UnixStream(mut sender) => {
+            sender.mut_context().format(LocalFormat);
+            sender.set_postfix("\0");
+            SyslogSender::UnixStream(sender)
+        }
+        sender => sender,
+    };
+    Ok(sender)
+}
+
+/// Create a Unix sender that sends messages to the well-known local syslog socket the same way
+/// glibc's `syslog(3)` does.
+///
+/// See also [`unix_local`].
+pub fn unix_local_well_known() -> io::Result<SyslogSender> {
+    unix_local(well_known_path())
+}
+
+fn well_known_path() -> &'static str {
     cfg_if::cfg_if! {
        if #[cfg(target_os = "macos")] {
             // NOTE: This may not work on Monterey (12.x) and above,
             //  see also https://github.com/python/cpython/issues/91070.
-            unix("/var/run/syslog")
+            "/var/run/syslog"
         } else if #[cfg(target_os = "freebsd")] {
-            unix("/var/run/log")
+            "/var/run/log"
         } else {
-            unix("/dev/log")
+            "/dev/log"
         }
     }
 }
@@ -94,14 +141,29 @@ impl UnixDatagramSender {
         &mut self.context
     }
 
-    /// Send a pre-formatted message.
-    pub fn send_formatted(&mut self, formatted: &[u8]) -> io::Result<()> {
+    pub(crate) fn into_parts(self) -> (UnixDatagram, SyslogContext) {
+        (self.socket, self.context)
+    }
+}
+
+impl Sender for UnixDatagramSender {
+    fn send_formatted(&mut self, formatted: &[u8]) -> io::Result<()> {
         self.socket.send(formatted)?;
         Ok(())
     }
-}
 
-impl_syslog_sender_common!(UnixDatagramSender);
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+
+    fn context(&self) -> &SyslogContext {
+        &self.context
+    }
+
+    fn context_mut(&mut self) -> &mut SyslogContext {
+        &mut self.context
+    }
+}
 
 /// A syslog sender that sends messages to a Unix stream socket.
 ///
@@ -112,6 +174,9 @@ pub struct UnixStreamSender {
     writer: BufWriter<UnixStream>,
     context: SyslogContext,
     postfix: Cow<'static, str>,
+    framing: Framing,
+    // set when a write timed out, since part of a message may have been written
+    poisoned: bool,
 }
 
 impl UnixStreamSender {
@@ -122,16 +187,26 @@ impl UnixStreamSender {
             writer: BufWriter::new(socket),
             context: SyslogContext::default(),
             postfix: Cow::Borrowed("\r\n"),
+            framing: Framing::NonTransparent,
+            poisoned: false,
         })
     }
 
     /// Set the postfix when formatting Syslog message.
     ///
-    /// Default is "\r\n". You can use empty string to set no postfix.
+    /// Default is "\r\n". You can use empty string to set no postfix, or "\0" to frame messages
+    /// as glibc's `syslog(3)` does.
     pub fn set_postfix(&mut self, postfix: impl Into<Cow<'static, str>>) {
         self.postfix = postfix.into();
     }
 
+    /// Set the framing of messages sent over the stream.
+    ///
+    /// Default is [`Framing::NonTransparent`], which terminates messages with the postfix.
+    pub fn set_framing(&mut self, framing: Framing) {
+        self.framing = framing;
+    }
+
     /// Set the context when formatting Syslog message.
     pub fn set_context(&mut self, context: SyslogContext) {
         self.context = context;
@@ -143,5 +218,79 @@ impl UnixStreamSender {
     }
 }
 
-impl_syslog_sender_common!(UnixStreamSender);
-impl_syslog_stream_send_formatted!(UnixStreamSender);
+impl_syslog_stream_sender!(UnixStreamSender);
+
+#[cfg(test)]
+mod tests {
+    use std::io::BufRead;
+    use std::io::BufReader;
+    use std::os::unix::net::UnixListener;
+    use std::path::PathBuf;
+
+    use super::*;
+    use crate::Severity;
+
+    fn socket_path(name: &str) -> PathBuf {
+        let path = std::env::temp_dir().join(format!("fasyslog-{name}-{}", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        path
+    }
+
+    fn configure(sender: &mut SyslogSender) {
+        sender
+            .context_mut()
+            .hostname("example.com")
+            .appname("app")
+            .procid("42");
+    }
+
+    #[test]
+    fn test_unix_local_datagram() {
+        let path = socket_path("local-dgram");
+        let receiver = UnixDatagram::bind(&path).unwrap();
+        let mut sender = unix_local(&path).unwrap();
+        assert!(matches!(sender, SyslogSender::UnixDatagram(_)));
+        configure(&mut sender);
+        sender.send(Severity::ERROR, "hello").unwrap();
+        sender.send_rfc3164(Severity::ERROR, "hello").unwrap();
+
+        let mut buf = [0; 1024];
+        let len = receiver.recv(&mut buf).unwrap();
+        let local = std::str::from_utf8(&buf[..len]).unwrap();
+        // "<11>Mmm dd hh:mm:ss app[42]: hello"
+        assert!(local.starts_with("<11>"), "{local}");
+        assert_eq!(&local[4 + 15..], " app[42]: hello");
+        let len = receiver.recv(&mut buf).unwrap();
+        let rfc3164 = std::str::from_utf8(&buf[..len]).unwrap();
+        assert!(rfc3164.contains(" example.com app[42]: "), "{rfc3164}");
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_unix_local_stream() {
+        let path = socket_path("local-stream");
+        let listener = UnixListener::bind(&path).unwrap();
+        let mut sender = unix_local(&path).unwrap();
+        assert!(matches!(sender, SyslogSender::UnixStream(_)));
+        let (stream, _) = listener.accept().unwrap();
+        configure(&mut sender);
+        sender.send(Severity::ERROR, "one").unwrap();
+        sender.send(Severity::ERROR, "two").unwrap();
+        sender.flush().unwrap();
+        drop(sender);
+
+        let mut reader = BufReader::new(stream);
+        for expected in ["one", "two"] {
+            let mut message = Vec::new();
+            reader.read_until(b'\0', &mut message).unwrap();
+            let message = String::from_utf8(message).unwrap();
+            assert!(message.starts_with("<11>"), "{message:?}");
+            assert!(
+                message.ends_with(&format!(" app[42]: {expected}\0")),
+                "{message:?}"
+            );
+            assert!(!message.contains("example.com"), "{message:?}");
+        }
+        std::fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,453 @@
+// Copyright 2024 FastLabs Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::ffi::CStr;
+use std::fmt;
+use std::fs::File;
+use std::io;
+use std::io::Write;
+use std::os::fd::AsRawFd;
+use std::os::unix::net::UnixDatagram;
+use std::path::Path;
+
+use nix::fcntl::fcntl;
+use nix::fcntl::FcntlArg;
+use nix::fcntl::SealFlag;
+use nix::sys::memfd::memfd_create;
+use nix::sys::memfd::MemFdCreateFlag;
+use nix::sys::socket::sendmsg;
+use nix::sys::socket::ControlMessage;
+use nix::sys::socket::MsgFlags;
+use nix::sys::socket::UnixAddr;
+
+use crate::format::SyslogContext;
+use crate::format::SyslogFormat;
+use crate::sender::Sender;
+use crate::Severity;
+
+/// Create a journald sender that sends messages to the well-known journal socket.
+///
+/// See also [systemd-journald.service(8)].
+///
+/// [systemd-journald.service(8)]: https://www.freedesktop.org/software/systemd/man/latest/systemd-journald.service.html
+pub fn journald_well_known() -> io::Result<JournaldSender> {
+    journald("/run/systemd/journal/socket")
+}
+
+/// Create a journald sender that sends messages to the given path.
+pub fn journald(path: impl AsRef<Path>) -> io::Result<JournaldSender> {
+    JournaldSender::connect(path)
+}
+
+/// A sender that sends messages to systemd-journald with the [native journal protocol].
+///
+/// Unlike sending to `/dev/log`, every message is sent as a set of journal fields:
+///
+/// * `MESSAGE` is the message;
+/// * `PRIORITY` is the numerical code of the [`Severity`];
+/// * `SYSLOG_FACILITY`, `SYSLOG_IDENTIFIER` and `SYSLOG_PID` are the facility, appname and procid
+///   of the [`SyslogContext`];
+/// * `SYSLOG_MSGID` is the MSGID passed to [`send_rfc5424`](Sender::send_rfc5424);
+/// * every [`SDParam`](crate::SDParam) of the structured data becomes a field named after the
+///   upper-cased PARAM-NAME. Names of fields that journald interprets, such as `MESSAGE`,
+///   `PRIORITY` or `SYSLOG_IDENTIFIER`, are prefixed with `SD_` and the upper-cased SD-ID, so that
+///   they cannot override the fields above.
+///
+/// Pre-formatted messages, such as those forwarded by [`ReconnectingSender`] and the other
+/// wrappers, have no severity to send. Their leading `<PRI>` is parsed into `PRIORITY` and
+/// `SYSLOG_FACILITY`, and stripped from `MESSAGE`.
+///
+/// Messages that are too large for a datagram are passed to journald as a sealed memfd.
+///
+/// [`ReconnectingSender`]: crate::sender::ReconnectingSender
+///
+/// [native journal protocol]: https://systemd.io/JOURNAL_NATIVE_PROTOCOL/
+#[derive(Debug)]
+pub struct JournaldSender {
+    socket: UnixDatagram,
+    context: SyslogContext,
+}
+
+impl JournaldSender {
+    /// Connect to a journal socket at the given path.
+    pub fn connect(path: impl AsRef<Path>) -> io::Result<Self> {
+        let socket = UnixDatagram::unbound()?;
+        socket.connect(path)?;
+        Ok(Self {
+            socket,
+            context: SyslogContext::default(),
+        })
+    }
+
+    /// Set the context when formatting Syslog message.
+    pub fn set_context(&mut self, context: SyslogContext) {
+        self.context = context;
+    }
+
+    /// Mutate the context when formatting Syslog message.
+    pub fn mut_context(&mut self) -> &mut SyslogContext {
+        &mut self.context
+    }
+}
+
+impl Sender for JournaldSender {
+    /// Send a pre-formatted message as the `MESSAGE` field.
+    ///
+    /// A leading `<PRI>` is sent as the `PRIORITY` and `SYSLOG_FACILITY` fields instead.
+    fn send_formatted(&mut self, formatted: &[u8]) -> io::Result<()> {
+        let (mut payload, message) = match split_priority(formatted) {
+            Some((priority, message)) => (self.encode_context(Some(priority)), message),
+            None => (self.encode_context(None), formatted),
+        };
+        encode_field(&mut payload, "MESSAGE", message);
+        self.send_payload(&payload)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+
+    fn context(&self) -> &SyslogContext {
+        &self.context
+    }
+
+    fn context_mut(&mut self) -> &mut SyslogContext {
+        &mut self.context
+    }
+
+    /// Send a message with the given severity as journal fields.
+    ///
+    /// The format only contributes its MSGID and structured data, since the journal records the
+    /// header fields on its own. The message is sent unformatted as the `MESSAGE` field.
+    fn send_message(
+        &mut self,
+        format: &dyn SyslogFormat,
+        severity: Severity,
+        message: &dyn fmt::Display,
+    ) -> io::Result<()> {
+        let records = self.context.records(severity, message);
+        if records.is_empty() {
+            return Ok(());
+        }
+        let priority = self.context.get_facility().code() << 3 | severity.code();
+        let mut header = self.encode_context(Some(priority));
+        if let Some(msgid) = format.msgid() {
+            encode_field(&mut header, "SYSLOG_MSGID", msgid.as_bytes());
+        }
+        for element in format.structured_data() {
+            for param in element.params() {
+                if let Some(name) = param_field_name(&element.id, &param.name) {
+                    encode_field(&mut header, &name, param.value.as_bytes());
+                }
+            }
+        }
+        self.send_records(&header, records)
+    }
+}
+
+impl JournaldSender {
+    // Send the records of a message as one entry each, sharing the fields encoded in `header`.
+    fn send_records(&mut self, header: &[u8], records: Vec<String>) -> io::Result<()> {
+        for message in records {
+            let mut payload = header.to_vec();
+            encode_field(&mut payload, "MESSAGE", message.as_bytes());
+            self.send_payload(&payload)?;
+        }
+        Ok(())
+    }
+
+    // Encode the fields of the context; the facility of `priority`, if any, replaces the one of
+    // the context.
+    fn encode_context(&self, priority: Option<u8>) -> Vec<u8> {
+        let mut payload = Vec::new();
+        let facility = match priority {
+            Some(priority) => {
+                let severity = (priority & 7).to_string();
+                encode_field(&mut payload, "PRIORITY", severity.as_bytes());
+                priority >> 3
+            }
+            None => self.context.get_facility().code(),
+        };
+        let facility = facility.to_string();
+        encode_field(&mut payload, "SYSLOG_FACILITY", facility.as_bytes());
+        if let Some(appname) = self.context.get_appname() {
+            encode_field(&mut payload, "SYSLOG_IDENTIFIER", appname.as_bytes());
+        }
+        if let Some(procid) = self.context.get_procid() {
+            encode_field(&mut payload, "SYSLOG_PID", procid.as_bytes());
+        }
+        payload
+    }
+
+    fn send_payload(&mut self, payload: &[u8]) -> io::Result<()> {
+        const EMSGSIZE: i32 = nix::errno::Errno::EMSGSIZE as i32;
+        const ENOBUFS: i32 = nix::errno::Errno::ENOBUFS as i32;
+        match self.socket.send(payload) {
+            Ok(_) => Ok(()),
+            Err(err) => match err.raw_os_error() {
+                Some(EMSGSIZE) | Some(ENOBUFS) => self.send_memfd(payload),
+                _ => Err(err),
+            },
+        }
+    }
+
+    // Large payloads are written to a sealed memfd whose descriptor is passed to journald with
+    // SCM_RIGHTS, as described in the native protocol.
+    fn send_memfd(&mut self, payload: &[u8]) -> io::Result<()> {
+        const NAME: &[u8] = b"fasyslog-journald\0";
+        let name = CStr::from_bytes_with_nul(NAME).expect("name must be nul-terminated");
+        let flags = MemFdCreateFlag::MFD_ALLOW_SEALING | MemFdCreateFlag::MFD_CLOEXEC;
+        let mut file = File::from(memfd_create(name, flags)?);
+        file.write_all(payload)?;
+        let seals = SealFlag::F_SEAL_SEAL
+            | SealFlag::F_SEAL_SHRINK
+            | SealFlag::F_SEAL_GROW
+            | SealFlag::F_SEAL_WRITE;
+        fcntl(file.as_raw_fd(), FcntlArg::F_ADD_SEALS(seals))?;
+        let fds = [file.as_raw_fd()];
+        let cmsgs = [ControlMessage::ScmRights(&fds)];
+        sendmsg::<UnixAddr>(
+            self.socket.as_raw_fd(),
+            &[],
+            &cmsgs,
+            MsgFlags::empty(),
+            None,
+        )?;
+        Ok(())
+    }
+}
+
+/// Split the leading `<PRI>` of a formatted message off, as its value and the rest of the message.
+fn split_priority(formatted: &[u8]) -> Option<(u8, &[u8])> {
+    let rest = formatted.strip_prefix(b"<")?;
+    let end = rest.iter().take(4).position(|&b| b == b'>')?;
+    let digits = std::str::from_utf8(&rest[..end]).ok()?;
+    if digits.is_empty() || !digits.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    let priority = digits
+        .parse::<u8>()
+        .ok()
+        .filter(|&priority| priority <= 191)?;
+    Some((priority, &rest[end + 1..]))
+}
+
+/// Encode a field as defined by the native journal protocol.
+///
+/// Values containing a newline are encoded in the binary form: the name, a newline, the length of
+/// the value as a little-endian 64-bit integer, the value, and a trailing newline.
+fn encode_field(payload: &mut Vec<u8>, name: &str, value: &[u8]) {
+    payload.extend_from_slice(name.as_bytes());
+    if value.contains(&b'\n') {
+        payload.push(b'\n');
+        payload.extend_from_slice(&(value.len() as u64).to_le_bytes());
+    } else {
+        payload.push(b'=');
+    }
+    payload.extend_from_slice(value);
+    payload.push(b'\n');
+}
+
+/// The user journal fields that have a meaning for journald, see [systemd.journal-fields(7)].
+///
+/// All the fields starting with `SYSLOG_` are reserved as well.
+///
+/// [systemd.journal-fields(7)]: https://www.freedesktop.org/software/systemd/man/latest/systemd.journal-fields.html
+const RESERVED_FIELDS: &[&str] = &[
+    "MESSAGE",
+    "MESSAGE_ID",
+    "PRIORITY",
+    "CODE_FILE",
+    "CODE_LINE",
+    "CODE_FUNC",
+    "ERRNO",
+    "INVOCATION_ID",
+    "USER_INVOCATION_ID",
+    "DOCUMENTATION",
+    "TID",
+    "UNIT",
+    "USER_UNIT",
+];
+
+/// Convert the PARAM-NAME of an SD-ELEMENT into a journal field name that is not reserved.
+fn param_field_name(id: &str, name: &str) -> Option<String> {
+    let field = field_name(name)?;
+    if field.starts_with("SYSLOG_") || RESERVED_FIELDS.contains(&field.as_str()) {
+        field_name(&format!("SD_{id}_{field}"))
+    } else {
+        Some(field)
+    }
+}
+
+/// Convert a PARAM-NAME into a valid journal field name.
+///
+/// Journal field names consist of upper-case ASCII letters, digits and underscores, must not start
+/// with an underscore or a digit, and are at most 64 characters long.
+fn field_name(name: &str) -> Option<String> {
+    let name = name
+        .chars()
+        .map(|c| match c {
+            'a'..='z' => c.to_ascii_uppercase(),
+            'A'..='Z' | '0'..='9' => c,
+            _ => '_',
+        })
+        .skip_while(|c| *c == '_' || c.is_ascii_digit())
+        .take(64)
+        .collect::<String>();
+    (!name.is_empty()).then_some(name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sender::ReconnectingSender;
+    use crate::SDElement;
+
+    #[test]
+    fn test_send_native_protocol() {
+        let path = std::env::temp_dir().join(format!("fasyslog-journald-{}", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let server = UnixDatagram::bind(&path).unwrap();
+
+        let mut sender = journald(&path).unwrap();
+        sender
+            .mut_context()
+            .facility(crate::Facility::DAEMON)
+            .appname("app")
+            .procid("42");
+        let mut element = SDElement::new("exampleSDID@32473").unwrap();
+        element.add_param("event-source", "Application").unwrap();
+        sender
+            .send_rfc5424(Severity::ERROR, Some("ID47"), vec![element], "a\nb")
+            .unwrap();
+
+        let mut buf = [0; 1024];
+        let n = server.recv(&mut buf).unwrap();
+        let mut expected = b"PRIORITY=3\nSYSLOG_FACILITY=3\nSYSLOG_IDENTIFIER=app\nSYSLOG_PID=42\n\
+            SYSLOG_MSGID=ID47\nEVENT_SOURCE=Application\nMESSAGE\n"
+            .to_vec();
+        expected.extend_from_slice(&3u64.to_le_bytes());
+        expected.extend_from_slice(b"a\nb\n");
+        assert_eq!(&buf[..n], &expected[..]);
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_send_large_payload_via_memfd() {
+        use std::io::IoSliceMut;
+        use std::io::Read;
+        use std::io::Seek;
+        use std::os::fd::FromRawFd;
+
+        use nix::sys::socket::recvmsg;
+        use nix::sys::socket::ControlMessageOwned;
+
+        let path = std::env::temp_dir().join(format!("fasyslog-memfd-{}", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let server = UnixDatagram::bind(&path).unwrap();
+
+        let mut sender = journald(&path).unwrap();
+        let message = "x".repeat(4 * 1024 * 1024);
+        sender
+            .send_rfc3164(Severity::INFORMATIONAL, &message)
+            .unwrap();
+
+        let mut buf = [0; 16];
+        let mut iov = [IoSliceMut::new(&mut buf)];
+        let mut cmsg = nix::cmsg_space!([std::os::fd::RawFd; 1]);
+        let msg = recvmsg::<UnixAddr>(
+            server.as_raw_fd(),
+            &mut iov,
+            Some(&mut cmsg),
+            MsgFlags::empty(),
+        )
+        .unwrap();
+        let fd = match msg.cmsgs().unwrap().next() {
+            Some(ControlMessageOwned::ScmRights(fds)) => fds[0],
+            cmsg => panic!("unexpected control message: {cmsg:?}"),
+        };
+        let mut payload = Vec::new();
+        let mut file = unsafe { File::from_raw_fd(fd) };
+        file.rewind().unwrap();
+        file.read_to_end(&mut payload).unwrap();
+        assert!(payload.starts_with(b"PRIORITY=6\n"));
+        assert!(payload.ends_with(format!("MESSAGE={message}\n").as_bytes()));
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_send_formatted_through_wrapper() {
+        let path = std::env::temp_dir().join(format!("fasyslog-wrapped-{}", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let server = UnixDatagram::bind(&path).unwrap();
+
+        let connect_path = path.clone();
+        let mut sender = ReconnectingSender::new(move || journald(&connect_path)).unwrap();
+        sender.context_mut().facility(crate::Facility::DAEMON);
+        sender.send_rfc3164(Severity::ERROR, "hello").unwrap();
+        sender.send_formatted(b"<1000>not a priority").unwrap();
+
+        let mut buf = [0; 1024];
+        let n = server.recv(&mut buf).unwrap();
+        let payload = std::str::from_utf8(&buf[..n]).unwrap();
+        assert!(
+            payload.starts_with("PRIORITY=3\nSYSLOG_FACILITY=3\n"),
+            "{payload}"
+        );
+        assert!(!payload.contains("MESSAGE=<"), "{payload}");
+        assert!(payload.ends_with(": hello\n"), "{payload}");
+
+        let n = server.recv(&mut buf).unwrap();
+        let payload = std::str::from_utf8(&buf[..n]).unwrap();
+        assert!(!payload.contains("PRIORITY="), "{payload}");
+        assert!(
+            payload.ends_with("MESSAGE=<1000>not a priority\n"),
+            "{payload}"
+        );
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_split_priority() {
+        assert_eq!(split_priority(b"<0>a"), Some((0, &b"a"[..])));
+        assert_eq!(split_priority(b"<191>1 -"), Some((191, &b"1 -"[..])));
+        assert_eq!(split_priority(b"<192>a"), None);
+        assert_eq!(split_priority(b"<>a"), None);
+        assert_eq!(split_priority(b"<+1>a"), None);
+        assert_eq!(split_priority(b"a"), None);
+    }
+
+    #[test]
+    fn test_reserved_param_names() {
+        assert_eq!(
+            param_field_name("origin", "software").as_deref(),
+            Some("SOFTWARE")
+        );
+        assert_eq!(
+            param_field_name("exampleSDID@32473", "message").as_deref(),
+            Some("SD_EXAMPLESDID_32473_MESSAGE")
+        );
+        assert_eq!(
+            param_field_name("meta", "syslog_identifier").as_deref(),
+            Some("SD_META_SYSLOG_IDENTIFIER")
+        );
+        assert_eq!(
+            param_field_name("meta", "Priority").as_deref(),
+            Some("SD_META_PRIORITY")
+        );
+    }
+}
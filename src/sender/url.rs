@@ -0,0 +1,485 @@
+// Copyright 2024 FastLabs Developers
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::io;
+use std::net::ToSocketAddrs;
+
+use crate::format::LocalFormat;
+use crate::format::Multiline;
+use crate::format::RFC3164Format;
+use crate::format::RFC5424Format;
+use crate::format::SdDaemonFormat;
+use crate::format::SyslogContext;
+use crate::sender::Framing;
+use crate::sender::Sender;
+use crate::sender::SyslogSender;
+use crate::Facility;
+
+/// Create a sender from a URL string.
+///
+/// Supported URLs are:
+///
+/// * `udp://host[:port]`: [`UdpSender`](crate::sender::UdpSender), port defaults to 514;
+/// * `tcp://host[:port]`: [`TcpSender`](crate::sender::TcpSender), port defaults to 601;
+/// * `tls://host[:port]`: `NativeTlsSender`, port defaults to 6514, requires features `native-tls`
+///   turned on, or `RustlsSender` with features `rustls` turned on instead;
+/// * `broadcast://[:port]`: UDP broadcast, port defaults to 514; a host is rejected, since messages
+///   are sent to the broadcast address;
+/// * (unix only) `unix:///path`, `unix+dgram:///path` and `unix+stream:///path`: Unix domain socket
+///   sender, either chosen automatically or of the given socket type.
+///
+/// Query parameters configure the sender:
+///
+/// * `facility`, `hostname`, `appname` and `procid` set the fields of the [`SyslogContext`];
+/// * `format` sets the default format of the context, one of `rfc3164`, `rfc5424`, `local` and
+///   `sd-daemon`;
+/// * `multiline` is `keep` or `split`;
+/// * (stream senders only) `framing` is `octet-counting` or `non-transparent`, and `postfix` sets
+///   the postfix of non-transparent framing;
+/// * (`tls` only) `domain` is the domain to validate the certificate against, defaults to the host,
+///   `ca` is the path to a PEM-encoded CA bundle to trust, and `cert` and `key` are the paths to a
+///   PEM-encoded client certificate and PKCS #8 private key.
+///
+/// Values are percent-decoded, so a NUL postfix is written as `postfix=%00`.
+///
+/// # Example
+///
+/// ```rust, no_run
+/// let sender =
+///     fasyslog::sender::from_url("tcp://127.0.0.1:601?appname=example&facility=local0").unwrap();
+/// ```
+pub fn from_url(url: &str) -> io::Result<SyslogSender> {
+    let (scheme, rest) = url
+        .split_once("://")
+        .ok_or_else(|| invalid_input(format!("missing scheme in URL: {url}")))?;
+    let (target, query) = match rest.split_once('?') {
+        Some((target, query)) => (target, Some(query)),
+        None => (rest, None),
+    };
+    let params = parse_query(query.unwrap_or_default())?;
+
+    let mut sender = match scheme {
+        "udp" => {
+            let (host, port) = parse_host_port(target, 514)?;
+            let remote = resolve(&host, port)?;
+            let local = if remote.is_ipv4() {
+                "0.0.0.0:0"
+            } else {
+                "[::]:0"
+            };
+            SyslogSender::Udp(super::udp(local, remote)?)
+        }
+        "tcp" => {
+            let (host, port) = parse_host_port(target, 601)?;
+            SyslogSender::Tcp(super::tcp((host.as_str(), port))?)
+        }
+        "tls" => {
+            let (host, port) = parse_host_port(target, 6514)?;
+            let param = |name| {
+                params
+                    .iter()
+                    .find(|(n, _)| n == name)
+                    .map(|(_, v)| v.as_str())
+            };
+            let params = TlsParams {
+                domain: param("domain"),
+                ca: param("ca"),
+                cert: param("cert"),
+                key: param("key"),
+            };
+            connect_tls(&host, port, &params)?
+        }
+        "broadcast" => {
+            let (host, port) = parse_host_port(target, 514)?;
+            if !host.is_empty() {
+                let message = format!("broadcast URL must not have a host: {url}");
+                return Err(invalid_input(message));
+            }
+            SyslogSender::Udp(super::broadcast(port)?)
+        }
+        #[cfg(unix)]
+        "unix" => super::unix(percent_decode(target)?)?,
+        #[cfg(unix)]
+        "unix+dgram" => SyslogSender::UnixDatagram(super::unix_datagram(percent_decode(target)?)?),
+        #[cfg(unix)]
+        "unix+stream" => SyslogSender::UnixStream(super::unix_stream(percent_decode(target)?)?),
+        _ => return Err(invalid_input(format!("unsupported URL scheme: {scheme}"))),
+    };
+
+    for (name, value) in &params {
+        match name.as_str() {
+            "domain" | "ca" | "cert" | "key" if scheme == "tls" => {}
+            "framing" => {
+                let framing = match value.as_str() {
+                    "octet-counting" => Framing::OctetCounting,
+                    "non-transparent" => Framing::NonTransparent,
+                    _ => return Err(invalid_input(format!("unknown framing: {value}"))),
+                };
+                set_framing(&mut sender, framing)?;
+            }
+            "postfix" => set_postfix(&mut sender, value.clone())?,
+            _ => set_context_param(sender.context_mut(), name, value)?,
+        }
+    }
+
+    Ok(sender)
+}
+
+/// Set a field of the context from its textual name and value.
+pub(crate) fn set_context_param(
+    context: &mut SyslogContext,
+    name: &str,
+    value: &str,
+) -> io::Result<()> {
+    match name {
+        "facility" => {
+            let facility = Facility::try_from(value)
+                .map_err(|_| invalid_input(format!("unknown facility: {value}")))?;
+            context.facility(facility);
+        }
+        "hostname" => {
+            context.hostname(value);
+        }
+        "appname" => {
+            context.appname(value);
+        }
+        "procid" => {
+            context.procid(value);
+        }
+        "format" => match value {
+            "rfc3164" => {
+                context.format(RFC3164Format);
+            }
+            "rfc5424" => {
+                context.format(RFC5424Format::default());
+            }
+            "local" => {
+                context.format(LocalFormat);
+            }
+            "sd-daemon" => {
+                context.format(SdDaemonFormat::new());
+            }
+            _ => return Err(invalid_input(format!("unknown format: {value}"))),
+        },
+        "multiline" => match value {
+            "keep" => {
+                context.multiline(Multiline::Keep);
+            }
+            "split" => {
+                context.multiline(Multiline::Split);
+            }
+            _ => return Err(invalid_input(format!("unknown multiline mode: {value}"))),
+        },
+        _ => return Err(invalid_input(format!("unknown parameter: {name}"))),
+    }
+    Ok(())
+}
+
+pub(crate) fn set_framing(sender: &mut SyslogSender, framing: Framing) -> io::Result<()> {
+    match sender {
+        SyslogSender::Tcp(sender) => sender.set_framing(framing),
+        #[cfg(feature = "native-tls")]
+        SyslogSender::NativeTlsSender(sender) => sender.set_framing(framing),
+        #[cfg(feature = "rustls")]
+        SyslogSender::RustlsSender(sender) => sender.set_framing(framing),
+        #[cfg(unix)]
+        SyslogSender::UnixStream(sender) => sender.set_framing(framing),
+        _ => return Err(invalid_input("framing is only supported by stream senders")),
+    }
+    Ok(())
+}
+
+pub(crate) fn set_postfix(sender: &mut SyslogSender, postfix: String) -> io::Result<()> {
+    match sender {
+        SyslogSender::Tcp(sender) => sender.set_postfix(postfix),
+        #[cfg(feature = "native-tls")]
+        SyslogSender::NativeTlsSender(sender) => sender.set_postfix(postfix),
+        #[cfg(feature = "rustls")]
+        SyslogSender::RustlsSender(sender) => sender.set_postfix(postfix),
+        #[cfg(unix)]
+        SyslogSender::UnixStream(sender) => sender.set_postfix(postfix),
+        SyslogSender::Stdio(sender) => sender.set_postfix(postfix),
+        _ => return Err(invalid_input("postfix is only supported by stream senders")),
+    }
+    Ok(())
+}
+
+/// The settings of a TLS connection given by a URL or a config.
+#[derive(Debug, Default)]
+#[cfg_attr(not(any(feature = "native-tls", feature = "rustls")), allow(dead_code))]
+pub(crate) struct TlsParams<'a> {
+    pub(crate) domain: Option<&'a str>,
+    pub(crate) ca: Option<&'a str>,
+    pub(crate) cert: Option<&'a str>,
+    pub(crate) key: Option<&'a str>,
+}
+
+#[cfg_attr(not(any(feature = "native-tls", feature = "rustls")), allow(dead_code))]
+impl<'a> TlsParams<'a> {
+    /// Returns the paths of the client certificate and key, if both are set.
+    fn identity(&self) -> io::Result<Option<(&'a str, &'a str)>> {
+        match (self.cert, self.key) {
+            (Some(cert), Some(key)) => Ok(Some((cert, key))),
+            (None, None) => Ok(None),
+            _ => Err(invalid_input(
+                "client certificate and key must be set together",
+            )),
+        }
+    }
+}
+
+#[cfg(feature = "native-tls")]
+pub(crate) fn connect_tls(host: &str, port: u16, params: &TlsParams) -> io::Result<SyslogSender> {
+    let mut builder = super::NativeTlsBuilder::new();
+    if let Some(ca) = params.ca {
+        builder = builder.ca_file(ca)?;
+    }
+    if let Some((cert, key)) = params.identity()? {
+        builder = builder.identity_pem_files(cert, key)?;
+    }
+    let sender = builder.connect((host, port), params.domain.unwrap_or(host))?;
+    Ok(SyslogSender::NativeTlsSender(sender))
+}
+
+#[cfg(all(feature = "rustls", not(feature = "native-tls")))]
+pub(crate) fn connect_tls(host: &str, port: u16, params: &TlsParams) -> io::Result<SyslogSender> {
+    use rustls::pki_types::pem::PemObject;
+    use rustls::pki_types::CertificateDer;
+    use rustls::pki_types::PrivateKeyDer;
+
+    fn invalid_file(what: &str, path: &str, err: impl std::fmt::Display) -> io::Error {
+        invalid_input(format!("invalid {what} {path}: {err}"))
+    }
+
+    let mut roots = rustls::RootCertStore::empty();
+    roots.extend(webpki_roots::TLS_SERVER_ROOTS.iter().cloned());
+    if let Some(ca) = params.ca {
+        let certs =
+            CertificateDer::pem_file_iter(ca).map_err(|err| invalid_file("CA file", ca, err))?;
+        for cert in certs {
+            let cert = cert.map_err(|err| invalid_file("CA file", ca, err))?;
+            roots
+                .add(cert)
+                .map_err(|err| invalid_file("CA file", ca, err))?;
+        }
+    }
+    let builder = super::rustls_config_builder(roots)?;
+    let config = match params.identity()? {
+        Some((cert, key)) => {
+            let certs = CertificateDer::pem_file_iter(cert)
+                .and_then(|certs| certs.collect::<Result<Vec<_>, _>>())
+                .map_err(|err| invalid_file("client certificate file", cert, err))?;
+            let key = PrivateKeyDer::from_pem_file(key)
+                .map_err(|err| invalid_file("client key file", key, err))?;
+            builder
+                .with_client_auth_cert(certs, key)
+                .map_err(|err| invalid_input(format!("invalid client identity: {err}")))?
+        }
+        None => builder.with_no_client_auth(),
+    };
+    let domain = params.domain.unwrap_or(host);
+    let sender = super::rustls_with((host, port), domain, std::sync::Arc::new(config))?;
+    Ok(SyslogSender::RustlsSender(sender))
+}
+
+#[cfg(not(any(feature = "native-tls", feature = "rustls")))]
+pub(crate) fn connect_tls(_: &str, _: u16, _: &TlsParams) -> io::Result<SyslogSender> {
+    Err(io::Error::new(
+        io::ErrorKind::Unsupported,
+        "TLS requires features `native-tls` or `rustls` turned on",
+    ))
+}
+
+pub(crate) fn resolve(host: &str, port: u16) -> io::Result<std::net::SocketAddr> {
+    (host, port).to_socket_addrs()?.next().ok_or_else(|| {
+        io::Error::new(
+            io::ErrorKind::NotFound,
+            format!("no address found for {host}:{port}"),
+        )
+    })
+}
+
+/// Split `host[:port]` into the host and the port, with `[v6addr]` brackets removed.
+pub(crate) fn parse_host_port(target: &str, default_port: u16) -> io::Result<(String, u16)> {
+    let target = target.trim_end_matches('/');
+    let (host, port) = match target.strip_prefix('[') {
+        Some(rest) => {
+            let (host, rest) = rest
+                .split_once(']')
+                .ok_or_else(|| invalid_input(format!("unterminated IPv6 address: {target}")))?;
+            (host, rest.strip_prefix(':'))
+        }
+        None => match target.split_once(':') {
+            Some((host, port)) => (host, Some(port)),
+            None => (target, None),
+        },
+    };
+    let port = match port {
+        Some(port) => port
+            .parse()
+            .map_err(|_| invalid_input(format!("invalid port: {port}")))?,
+        None => default_port,
+    };
+    Ok((percent_decode(host)?, port))
+}
+
+fn parse_query(query: &str) -> io::Result<Vec<(String, String)>> {
+    query
+        .split('&')
+        .filter(|pair| !pair.is_empty())
+        .map(|pair| {
+            let (name, value) = pair.split_once('=').unwrap_or((pair, ""));
+            Ok((percent_decode(name)?, percent_decode(value)?))
+        })
+        .collect()
+}
+
+fn percent_decode(s: &str) -> io::Result<String> {
+    let mut bytes = Vec::with_capacity(s.len());
+    let mut iter = s.bytes();
+    while let Some(b) = iter.next() {
+        if b == b'%' {
+            let hex = [iter.next(), iter.next()];
+            let decoded = match hex {
+                [Some(hi), Some(lo)] => std::str::from_utf8(&[hi, lo])
+                    .ok()
+                    .and_then(|hex| u8::from_str_radix(hex, 16).ok()),
+                _ => None,
+            };
+            bytes.push(
+                decoded.ok_or_else(|| invalid_input(format!("invalid percent-encoding: {s}")))?,
+            );
+        } else {
+            bytes.push(b);
+        }
+    }
+    String::from_utf8(bytes).map_err(|_| invalid_input(format!("invalid UTF-8: {s}")))
+}
+
+pub(crate) fn invalid_input(message: impl Into<String>) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidInput, message.into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_host_port() {
+        let parse = |target| parse_host_port(target, 514).unwrap();
+        assert_eq!(parse("example.com"), ("example.com".to_string(), 514));
+        assert_eq!(
+            parse("example.com:1514/"),
+            ("example.com".to_string(), 1514)
+        );
+        assert_eq!(parse("[::1]:601"), ("::1".to_string(), 601));
+        assert_eq!(parse(":5514"), ("".to_string(), 5514));
+        assert!(parse_host_port("example.com:syslog", 514).is_err());
+    }
+
+    #[test]
+    fn test_from_url() {
+        let url = "udp://127.0.0.1:5514?facility=local0&appname=my%20app&format=rfc5424";
+        let sender = from_url(url).unwrap();
+        assert!(matches!(sender, SyslogSender::Udp(_)));
+        assert_eq!(sender.context().get_facility(), Facility::LOCAL0);
+        assert_eq!(sender.context().get_appname(), Some("my app"));
+
+        let err = from_url("udp://127.0.0.1:5514?postfix=%00").unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+        let err = from_url("http://127.0.0.1").unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+    }
+
+    #[test]
+    fn test_from_url_tcp() {
+        use std::io::Read;
+        use std::net::TcpListener;
+
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let urls = [
+            format!("tcp://127.0.0.1:{port}?framing=octet-counting&appname=app"),
+            format!("tcp://127.0.0.1:{port}/?postfix=%0A&framing=non-transparent"),
+        ];
+        let mut received = Vec::new();
+        for url in urls {
+            let mut sender = from_url(&url).unwrap();
+            assert!(matches!(sender, SyslogSender::Tcp(_)));
+            let (mut stream, _) = listener.accept().unwrap();
+            sender.send_formatted(b"hello").unwrap();
+            sender.flush().unwrap();
+            drop(sender);
+            let mut message = String::new();
+            stream.read_to_string(&mut message).unwrap();
+            received.push(message);
+        }
+        assert_eq!(received, ["5 hello", "hello\n"]);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_from_url_unix() {
+        use std::io::Read;
+        use std::os::unix::net::UnixDatagram;
+        use std::os::unix::net::UnixListener;
+
+        let dir = std::env::temp_dir();
+        let stream_path = dir.join(format!("fasyslog-url-stream-{}", std::process::id()));
+        let dgram_path = dir.join(format!("fasyslog-url-dgram-{}", std::process::id()));
+        let _ = std::fs::remove_file(&stream_path);
+        let _ = std::fs::remove_file(&dgram_path);
+
+        let listener = UnixListener::bind(&stream_path).unwrap();
+        let url = format!("unix://{}?postfix=%00", stream_path.display());
+        let mut sender = from_url(&url).unwrap();
+        assert!(matches!(sender, SyslogSender::UnixStream(_)));
+        let (mut stream, _) = listener.accept().unwrap();
+        sender.send_formatted(b"hello").unwrap();
+        sender.flush().unwrap();
+        drop(sender);
+        let mut message = String::new();
+        stream.read_to_string(&mut message).unwrap();
+        assert_eq!(message, "hello\0");
+
+        let receiver = UnixDatagram::bind(&dgram_path).unwrap();
+        let url = format!("unix+dgram://{}?multiline=split", dgram_path.display());
+        let mut sender = from_url(&url).unwrap();
+        assert!(matches!(sender, SyslogSender::UnixDatagram(_)));
+        sender.send_formatted(b"hello").unwrap();
+        let mut buf = [0; 16];
+        let n = receiver.recv(&mut buf).unwrap();
+        assert_eq!(&buf[..n], b"hello");
+        let url = format!(
+            "unix+dgram://{}?framing=octet-counting",
+            dgram_path.display()
+        );
+        let err = from_url(&url).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+
+        std::fs::remove_file(&stream_path).unwrap();
+        std::fs::remove_file(&dgram_path).unwrap();
+    }
+
+    #[test]
+    fn test_from_url_broadcast() {
+        let err = from_url("broadcast://example.com:5514").unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+        assert!(err.to_string().contains("must not have a host"), "{err}");
+
+        let sender = from_url("broadcast://:5514?appname=app").unwrap();
+        assert!(matches!(sender, SyslogSender::Udp(_)));
+        assert_eq!(sender.context().get_appname(), Some("app"));
+    }
+}
@@ -109,3 +109,88 @@ impl fmt::Display for Severity {
         write!(f, "{}", self.label())
     }
 }
+
+/// A set of severities to send, like the log priority mask of [setlogmask(3)].
+///
+/// Senders drop messages whose severity is not in the mask of their context before formatting
+/// them. See also [`SyslogContext::mask`](crate::format::SyslogContext::mask).
+///
+/// [setlogmask(3)]: https://man7.org/linux/man-pages/man3/setlogmask.3.html
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct SeverityMask(u8);
+
+impl SeverityMask {
+    /// A mask that contains all severities.
+    pub const ALL: SeverityMask = SeverityMask(0xFF);
+
+    /// A mask that contains no severity.
+    pub const NONE: SeverityMask = SeverityMask(0);
+
+    /// Returns a mask of the given severity and all more severe ones, like `LOG_UPTO`.
+    pub const fn up_to(severity: Severity) -> Self {
+        SeverityMask(((1u16 << (severity as u8 + 1)) - 1) as u8)
+    }
+
+    /// Returns a mask of only the given severity, like `LOG_MASK`.
+    pub const fn only(severity: Severity) -> Self {
+        SeverityMask(1 << severity as u8)
+    }
+
+    /// Returns this mask with the given severity added.
+    pub const fn with(self, severity: Severity) -> Self {
+        SeverityMask(self.0 | Self::only(severity).0)
+    }
+
+    /// Returns this mask with the given severity removed.
+    pub const fn without(self, severity: Severity) -> Self {
+        SeverityMask(self.0 & !Self::only(severity).0)
+    }
+
+    /// Returns `true` if the mask contains the given severity.
+    pub const fn contains(self, severity: Severity) -> bool {
+        self.0 & Self::only(severity).0 != 0
+    }
+
+    /// Returns the mask as bits, where bit `n` is set for the severity of numerical code `n`.
+    pub const fn bits(self) -> u8 {
+        self.0
+    }
+
+    /// Create a mask from bits, where bit `n` is set for the severity of numerical code `n`.
+    pub const fn from_bits(bits: u8) -> Self {
+        SeverityMask(bits)
+    }
+}
+
+impl Default for SeverityMask {
+    fn default() -> Self {
+        SeverityMask::ALL
+    }
+}
+
+impl FromIterator<Severity> for SeverityMask {
+    fn from_iter<I: IntoIterator<Item = Severity>>(iter: I) -> Self {
+        iter.into_iter()
+            .fold(SeverityMask::NONE, SeverityMask::with)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_severity_mask() {
+        assert_eq!(SeverityMask::up_to(Severity::DEBUG), SeverityMask::ALL);
+        assert_eq!(SeverityMask::up_to(Severity::EMERGENCY).bits(), 0b1);
+        let mask = SeverityMask::up_to(Severity::NOTICE);
+        assert!(mask.contains(Severity::EMERGENCY));
+        assert!(mask.contains(Severity::NOTICE));
+        assert!(!mask.contains(Severity::INFORMATIONAL));
+        let mask = [Severity::ERROR, Severity::DEBUG]
+            .into_iter()
+            .collect::<SeverityMask>();
+        assert_eq!(mask.bits(), 0b1000_1000);
+        assert!(!mask.without(Severity::ERROR).contains(Severity::ERROR));
+    }
+}
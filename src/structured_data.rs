@@ -120,6 +120,11 @@ impl SDElement {
         Ok(())
     }
 
+    /// Returns the SD-PARAMs of the SD-ELEMENT.
+    pub fn params(&self) -> &[SDParam] {
+        &self.params
+    }
+
     // Registered SD-IDs as documented in RFC-5424 §9.2.
     const fn registered_ids() -> [&'static str; 3] {
         ["timeQuality", "origin", "meta"]